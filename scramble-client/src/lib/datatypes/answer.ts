@@ -3,6 +3,7 @@ export class Answer {
     answer: string = "";
     score: number = 0;
     definition: string = "";
+    answer_time_ms: number = 0;
 
     constructor(name: string, answer: string) {
         this.player = name;
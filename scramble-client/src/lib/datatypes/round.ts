@@ -2,7 +2,16 @@ import { Answer } from "./answer"
 
 export class Round {
     letters: Array<string> = [];
+    difficulty: string = "";
+    bonuses: {
+        letter_multipliers: { [index: number]: number };
+        word_multiplier: { index: number; multiplier: number } | null;
+    } = { letter_multipliers: {}, word_multiplier: null };
+    constraint: { [kind: string]: { letter?: string; length?: number; index?: number } } | null = null;
     answers: Array<Answer> = [];
+    word_lists: { [player: string]: Array<Answer> } = {};
+    started_at_ms: number | null = null;
+    deadline_ms: number | null = null;
     guesses_used: Object = {};
     best_answers: Array<any> = [];
 
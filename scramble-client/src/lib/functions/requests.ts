@@ -50,6 +50,79 @@ export async function postAnswer(game_name: string | null, name: string | null,
 	return response;
 }
 
+export async function postStart(game_name: string | null, name: string | null) {
+	const response: Response = await fetch(getBaseServerPath() + game_name + '/start', {
+		method: 'POST',
+		headers: { 'Content-Type': 'application/json' },
+		body: JSON.stringify({
+			player: name
+		})
+	});
+	return response;
+}
+
+export async function postRerollVote(game_name: string | null, name: string | null) {
+	const response: Response = await fetch(getBaseServerPath() + game_name + '/reroll', {
+		method: 'POST',
+		headers: { 'Content-Type': 'application/json' },
+		body: JSON.stringify({
+			player: name
+		})
+	});
+	return response;
+}
+
+export async function postHint(game_name: string | null, name: string | null) {
+	const response: Response = await fetch(getBaseServerPath() + game_name + '/hint', {
+		method: 'POST',
+		headers: { 'Content-Type': 'application/json' },
+		body: JSON.stringify({
+			player: name
+		})
+	});
+	return response;
+}
+
+export async function postChallenge(
+	game_name: string | null,
+	name: string | null,
+	round: number,
+	answer_player: string,
+	answer: string
+) {
+	const response: Response = await fetch(getBaseServerPath() + game_name + '/challenge', {
+		method: 'POST',
+		headers: { 'Content-Type': 'application/json' },
+		body: JSON.stringify({
+			player: name,
+			round,
+			answer_player,
+			answer
+		})
+	});
+	return response;
+}
+
+export async function postChallengeVote(
+	game_name: string | null,
+	name: string | null,
+	challenge_id: number,
+	agree: boolean
+) {
+	const response: Response = await fetch(
+		getBaseServerPath() + game_name + '/challenge/' + challenge_id + '/vote',
+		{
+			method: 'POST',
+			headers: { 'Content-Type': 'application/json' },
+			body: JSON.stringify({
+				player: name,
+				agree
+			})
+		}
+	);
+	return response;
+}
+
 export async function deletePlayerFromGame(game_name: string | null, name: string | null) {
 	const response: Response = await fetch(getBaseServerPath() + game_name + '/exit', {
 		method: 'DELETE',
@@ -86,6 +159,14 @@ export async function getScore(game_name: string | null) {
 	return response;
 }
 
+export async function getTiles(game_name: string | null) {
+	const response: Response = await fetch(getBaseServerPath() + game_name + '/tiles', {
+		method: 'GET',
+		headers: { 'Content-Type': 'application/json' }
+	});
+	return response;
+}
+
 export async function postChangeQuestion(game_name: string | null) {
 	const response: Response = await fetch(getBaseServerPath() + game_name + '/change_question', {
 		method: 'POST',
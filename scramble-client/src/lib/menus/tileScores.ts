@@ -1,28 +1,13 @@
-export const tileScores: Map<string, number> = new Map([
-    ['E', 1],
-    ['A', 1],
-    ['I', 1],
-    ['O', 1],
-    ['N', 1],
-    ['R', 1],
-    ['T', 1],
-    ['L', 1],
-    ['S', 1],
-    ['U', 1],
-    ['D', 2],
-    ['G', 2],
-    ['B', 3],
-    ['C', 3],
-    ['M', 3],
-    ['P', 3],
-    ['F', 4],
-    ['H', 4],
-    ['V', 4],
-    ['W', 4],
-    ['Y', 4],
-    ['K', 5],
-    ['J', 8],
-    ['X', 8],
-    ['Q', 10],
-    ['Z', 10]
-]);
\ No newline at end of file
+import { writable } from 'svelte/store';
+import { getTiles } from '$lib/functions/requests';
+
+// Empty until the game's tiles have been loaded from the server, which decides the scores
+export const tileScores = writable<Map<string, number>>(new Map());
+
+export async function loadTileScores(game_name: string | null) {
+    const response = await getTiles(game_name);
+    if (response.ok) {
+        const tiles: Array<{ letter: string; points: number }> = await response.json();
+        tileScores.set(new Map(tiles.map((tile) => [tile.letter, tile.points])));
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dictionary::WordInfo;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct BotSettings {
+    /// The fraction of the round's best score the bot aims for, from 0 to 1
+    pub(crate) skill: f64,
+    /// The longest word the bot will play
+    pub(crate) max_word_length: Option<u32>,
+    /// How long the bot waits after a round starts before answering
+    pub(crate) answer_delay_seconds: u64,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self {
+            skill: 0.5,
+            max_word_length: None,
+            answer_delay_seconds: 10,
+        }
+    }
+}
+
+impl BotSettings {
+    pub(crate) fn is_valid(&self) -> bool {
+        (0.0..=1.0).contains(&self.skill) && self.max_word_length.is_none_or(|max| max >= 2)
+    }
+
+    /// Pick the word whose score is closest to the bot's target score
+    pub(crate) fn choose_word<'a>(&self, words: &'a [WordInfo]) -> Option<&'a WordInfo> {
+        let allowed = || {
+            words.iter().filter(|word| {
+                self.max_word_length
+                    .is_none_or(|max| word.tiles.len() <= max as usize)
+            })
+        };
+        let best_score = allowed().map(|word| word.score).max()?;
+        let target = best_score as f64 * self.skill;
+        allowed().min_by(|a, b| {
+            let a_distance = (a.score as f64 - target).abs();
+            let b_distance = (b.score as f64 - target).abs();
+            a_distance.total_cmp(&b_distance)
+        })
+    }
+}
+
+#[test]
+fn test_choose_word() {
+    let word = |word: &str, score| WordInfo {
+        word: word.to_string(),
+        score,
+        definition: String::new(),
+        tiles: word.chars().map(String::from).collect(),
+    };
+    let words = vec![word("REMORSE", 20), word("MORSE", 10), word("RE", 2)];
+    let bot = |skill, max_word_length| BotSettings {
+        skill,
+        max_word_length,
+        ..Default::default()
+    };
+    assert_eq!(bot(1.0, None).choose_word(&words).unwrap().word, "REMORSE");
+    assert_eq!(bot(0.5, None).choose_word(&words).unwrap().word, "MORSE");
+    assert_eq!(bot(0.0, None).choose_word(&words).unwrap().word, "RE");
+    assert_eq!(bot(1.0, Some(5)).choose_word(&words).unwrap().word, "MORSE");
+    assert!(bot(1.0, Some(5)).choose_word(&[]).is_none());
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Player;
+
+/// What a challenge asks the players to decide about an answer
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) enum ChallengeKind {
+    /// Take away the score of a word the dictionary accepted
+    Reject,
+    /// Score a guess the dictionary rejected
+    Accept,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) enum ChallengeOutcome {
+    /// A majority agreed with the challenge and the score was changed
+    Upheld,
+    /// Too many players disagreed for the challenge to pass
+    Dismissed,
+}
+
+/// A vote on whether the dictionary was wrong about an answer, kept once resolved as a record
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Challenge {
+    /// The index of the round the answer was given in
+    pub(crate) round: usize,
+    /// The player whose answer is challenged
+    pub(crate) player: Player,
+    /// The challenged word
+    pub(crate) answer: String,
+    /// Whether the challenge is to reject or accept the word
+    pub(crate) kind: ChallengeKind,
+    /// The player who raised the challenge
+    pub(crate) challenger: Player,
+    /// Whether each player who voted agrees with the challenge
+    pub(crate) votes: HashMap<Player, bool>,
+    /// The result once enough players have voted
+    pub(crate) outcome: Option<ChallengeOutcome>,
+}
+
+impl Challenge {
+    /// The result of the challenge if the votes so far decide it
+    pub(crate) fn tally(&self, voters: usize) -> Option<ChallengeOutcome> {
+        let agree = self.votes.values().filter(|agree| **agree).count();
+        let disagree = self.votes.len() - agree;
+        if agree * 2 > voters {
+            Some(ChallengeOutcome::Upheld)
+        } else if disagree * 2 >= voters {
+            Some(ChallengeOutcome::Dismissed)
+        } else {
+            None
+        }
+    }
+}
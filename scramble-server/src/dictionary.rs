@@ -1,120 +1,371 @@
 use std::{
+    borrow::Cow,
     collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
 };
 
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{seq::SliceRandom, thread_rng, Rng};
 use rocket::tokio;
 use serde::{Deserialize, Serialize};
 
-use crate::types::ScoringMethod;
-
-struct Tile {
-    letter: char,
-    points: u32,
-    count: u32,
-}
-
-macro_rules! new_tile {
-    ($letter:expr, $points:expr, $count:expr) => {
-        Tile {
-            letter: $letter,
-            points: $points,
-            count: $count,
-        }
-    };
-}
+use crate::{
+    language::{LanguagePack, Normalization},
+    scoring::{Bonuses, ScoringContext},
+    tiles::TileSet,
+    types::{Difficulty, Error, GameSettings, RackConstraints, Result, RoundConstraint},
+};
 
-#[test]
-fn test_tiles() {
-    let num_tiles: u32 = TILES.iter().map(|t| t.count).sum();
-    assert_eq!(num_tiles, 98);
-    let total_points: u32 = TILES.iter().map(|t| t.points * t.count).sum::<u32>();
-    assert_eq!(total_points, 187);
-}
-
-const TILES: [Tile; 26] = [
-    // new_tile!('?', 0, 2),
-    new_tile!('E', 1, 12),
-    new_tile!('A', 1, 9),
-    new_tile!('I', 1, 9),
-    new_tile!('O', 1, 8),
-    new_tile!('N', 1, 6),
-    new_tile!('R', 1, 6),
-    new_tile!('T', 1, 6),
-    new_tile!('L', 1, 4),
-    new_tile!('S', 1, 4),
-    new_tile!('U', 1, 4),
-    new_tile!('D', 2, 4),
-    new_tile!('G', 2, 3),
-    new_tile!('B', 3, 2),
-    new_tile!('C', 3, 2),
-    new_tile!('M', 3, 2),
-    new_tile!('P', 3, 2),
-    new_tile!('F', 4, 2),
-    new_tile!('H', 4, 2),
-    new_tile!('V', 4, 2),
-    new_tile!('W', 4, 2),
-    new_tile!('Y', 4, 2),
-    new_tile!('K', 5, 1),
-    new_tile!('J', 8, 1),
-    new_tile!('X', 8, 1),
-    new_tile!('Q', 10, 1),
-    new_tile!('Z', 10, 1),
-];
+/// The number of sets of letters to draw before giving up on the rack constraints
+const MAX_RACK_ATTEMPTS: u32 = 1000;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WordInfo {
     pub word: String,
     pub score: u32,
     pub definition: String,
+    /// The tiles needed to spell the word
+    #[serde(skip)]
+    pub(crate) tiles: Vec<String>,
+}
+
+/// A set of letters to spell words from along with how hard it is to play
+#[derive(Debug, Clone)]
+pub(crate) struct Rack {
+    pub(crate) letters: Vec<String>,
+    pub(crate) difficulty: Difficulty,
+    /// The bonus squares on the letters
+    pub(crate) bonuses: Bonuses,
+    /// The rule every answer with the letters must follow
+    pub(crate) constraint: Option<RoundConstraint>,
+}
+
+struct RackStats {
+    /// The number of playable words that can be spelled with the letters
+    playable_words: usize,
+    /// The length of the longest playable word, if there is one
+    longest_word_length: Option<usize>,
+}
+
+/// What a game does with words the dictionary flags as offensive
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) enum OffensiveWordFilter {
+    /// Offensive words are treated like any other word
+    #[default]
+    Off,
+    /// Offensive words are playable but never shown as best answers
+    Hide,
+    /// Offensive words are neither playable nor shown
+    Block,
 }
 
 pub struct Dictionary {
     playable_words: HashMap<String, WordInfo>,
-    letter_scores: HashMap<char, u32>,
-    all_tiles: Vec<char>,
+    /// The words flagged as offensive, which may or may not be in the word list
+    offensive_words: HashSet<String>,
+    /// The tile set used by games that do not bring their own
+    tile_set: TileSet,
+    normalization: Normalization,
+    vowels: Vec<String>,
 }
 
 impl Dictionary {
+    #[cfg(test)]
     pub fn new(path: &str) -> Self {
-        let all_tiles = TILES
-            .iter()
-            .flat_map(|tile| vec![tile.letter; tile.count as usize])
-            .collect();
+        Self::from_language_pack(LanguagePack::english(path, TileSet::default()))
+    }
+
+    pub(crate) fn from_language_pack(pack: LanguagePack) -> Self {
         let mut words = Self {
             playable_words: HashMap::new(),
-            letter_scores: TILES.iter().map(|t| (t.letter, t.points)).collect(),
-            all_tiles,
+            offensive_words: HashSet::new(),
+            tile_set: TileSet::default(),
+            normalization: pack.normalization,
+            vowels: Vec::new(),
         };
-        words.playable_words = words.read_words(path);
+        words.tile_set = pack.tile_set.normalized(|s| words.normalize(s));
+        words.vowels = pack.vowels.iter().map(|s| words.normalize(s)).collect();
+        words.playable_words = words.read_words(&pack.word_list);
+        if let Some(path) = &pack.offensive_words {
+            words.offensive_words = words.read_word_set(path);
+        }
         words
     }
 
-    pub(crate) fn get_random_letters(
-        &self,
-        size: usize,
-        banned_letters: &HashSet<char>,
-    ) -> Vec<char> {
+    /// Put a word or letter in the form used by the word list and tiles
+    pub(crate) fn normalize(&self, s: &str) -> String {
+        self.normalization.normalize(s)
+    }
+
+    /// Normalize the letters a player typed into the game settings
+    pub(crate) fn normalize_settings(&self, settings: &mut GameSettings) {
+        settings.banned_letters = settings
+            .banned_letters
+            .iter()
+            .map(|letter| self.normalize(letter))
+            .collect();
+        settings.tile_set = settings
+            .tile_set
+            .as_ref()
+            .map(|tile_set| tile_set.normalized(|s| self.normalize(s)));
+        settings.allowed_words = settings
+            .allowed_words
+            .iter()
+            .map(|(word, definition)| (self.normalize(word), definition.clone()))
+            .collect();
+        settings.blocked_words = settings
+            .blocked_words
+            .iter()
+            .map(|word| self.normalize(word))
+            .collect();
+    }
+
+    /// Split a normalized word into the tiles needed to spell it
+    pub(crate) fn tokenize(&self, word: &str) -> Option<Vec<String>> {
+        self.tile_set.tokenize(word)
+    }
+
+    /// The tile set a game is played with
+    pub(crate) fn tile_set<'a>(&'a self, settings: &'a GameSettings) -> &'a TileSet {
+        settings.tile_set.as_ref().unwrap_or(&self.tile_set)
+    }
+
+    pub(crate) fn get_random_letters(&self, settings: &GameSettings) -> Result<Rack> {
+        let size = settings.number_of_tiles as usize;
         let mut rng = thread_rng();
-        loop {
-            let mut all_tiles: Vec<char> = self
-                .all_tiles
-                .iter()
-                .cloned()
-                .filter(|tile| !banned_letters.contains(tile))
-                .collect();
+        let mut all_tiles: Vec<String> = self
+            .tile_set(settings)
+            .all_tiles()
+            .into_iter()
+            .filter(|tile| !settings.banned_letters.contains(tile))
+            .collect();
+        if all_tiles.len() < size {
+            return Err(Error::RackConstraintsUnsatisfiable);
+        }
+        for _ in 0..MAX_RACK_ATTEMPTS {
             all_tiles.shuffle(&mut rng);
-            all_tiles.truncate(size);
-            if self.has_playable_word(&all_tiles) {
-                return all_tiles;
+            let letters = &all_tiles[..size];
+            if !self.satisfies_letter_constraints(letters, &settings.rack_constraints) {
+                continue;
+            }
+            // The dictionary scan is the expensive check so it goes last
+            let stats = self.rack_stats(letters, settings);
+            let difficulty = Difficulty::from_playable_words(stats.playable_words, size);
+            if !Self::satisfies_word_constraints(letters, &stats, &settings.rack_constraints)
+                || settings.difficulty.is_some_and(|d| d != difficulty)
+            {
+                continue;
             }
+            let constraint = if settings.round_constraints {
+                let constraint = self.random_constraint(letters, settings, &mut rng);
+                // Every round needs at least one word that follows its constraint
+                let tile_set = self.tile_set(settings);
+                if !self.game_words(settings).any(|info| {
+                    Self::spellings_with_letters(letters, &info.tiles, tile_set)
+                        .any(|tiles| constraint.is_met(letters, &tiles))
+                }) {
+                    continue;
+                }
+                Some(constraint)
+            } else {
+                None
+            };
+            let bonuses = if settings.bonus_squares {
+                Bonuses::random(size, &mut rng)
+            } else {
+                Bonuses::default()
+            };
+            return Ok(Rack {
+                letters: letters.to_vec(),
+                difficulty,
+                bonuses,
+                constraint,
+            });
+        }
+        Err(Error::RackConstraintsUnsatisfiable)
+    }
+
+    /// Random letters for a game's next round, loosening the rules for the letters if they cannot be met
+    pub(crate) fn get_next_letters(&self, settings: &GameSettings) -> Result<Rack> {
+        self.get_random_letters(settings).or_else(|_| {
+            let relaxed = GameSettings {
+                difficulty: None,
+                rack_constraints: RackConstraints::default(),
+                round_constraints: false,
+                ..settings.clone()
+            };
+            self.get_random_letters(&relaxed)
+        })
+    }
+
+    fn random_constraint(
+        &self,
+        letters: &[String],
+        settings: &GameSettings,
+        rng: &mut impl Rng,
+    ) -> RoundConstraint {
+        match rng.gen_range(0..4) {
+            0 => {
+                // Make players use the rarest letter, like the Q
+                let tile_set = self.tile_set(settings);
+                let rarest = letters
+                    .iter()
+                    .max_by_key(|letter| tile_set.letter_rarity(letter));
+                RoundConstraint::MustUseLetter {
+                    letter: rarest.unwrap().clone(),
+                }
+            }
+            1 => RoundConstraint::MustStartWith {
+                letter: letters.choose(rng).unwrap().clone(),
+            },
+            2 => RoundConstraint::MinimumLength {
+                length: RoundConstraint::MINIMUM_LENGTH.min(letters.len()),
+            },
+            _ => RoundConstraint::MustUseTile {
+                index: rng.gen_range(0..letters.len()),
+            },
+        }
+    }
+
+    fn satisfies_letter_constraints(
+        &self,
+        letters: &[String],
+        constraints: &RackConstraints,
+    ) -> bool {
+        let vowels = letters.iter().filter(|c| self.vowels.contains(c)).count() as u32;
+        let distinct_letters = letters.iter().collect::<HashSet<_>>().len();
+        let duplicate_letters = (letters.len() - distinct_letters) as u32;
+        !(constraints.min_vowels.is_some_and(|min| vowels < min)
+            || constraints.max_vowels.is_some_and(|max| vowels > max)
+            || constraints
+                .max_duplicate_letters
+                .is_some_and(|max| duplicate_letters > max))
+    }
+
+    fn satisfies_word_constraints(
+        letters: &[String],
+        stats: &RackStats,
+        constraints: &RackConstraints,
+    ) -> bool {
+        let min_length = Self::min_best_word_length(letters, constraints);
+        stats
+            .longest_word_length
+            .is_some_and(|length| length >= min_length)
+    }
+
+    /// The length the longest playable word must reach for the letters to be used
+    fn min_best_word_length(letters: &[String], constraints: &RackConstraints) -> usize {
+        if constraints.require_bingo {
+            letters.len()
+        } else {
+            constraints.min_best_word_length.unwrap_or(0) as usize
         }
     }
 
     pub fn get_word_info_if_playable(&self, s: &str) -> Option<&WordInfo> {
-        self.playable_words.get(&s.to_ascii_uppercase())
+        self.playable_words.get(&self.normalize(s))
+    }
+
+    /// The info for a word if it is playable with a game's allowed and blocked words
+    pub(crate) fn get_game_word_info<'a>(
+        &'a self,
+        s: &str,
+        settings: &GameSettings,
+    ) -> Option<Cow<'a, WordInfo>> {
+        let word = self.normalize(s);
+        if settings.blocked_words.contains(&word)
+            || (settings.offensive_words == OffensiveWordFilter::Block
+                && self.offensive_words.contains(&word))
+        {
+            return None;
+        }
+        if let Some(definition) = settings.allowed_words.get(&word) {
+            return self
+                .allowed_word_info(&word, definition, settings)
+                .map(Cow::Owned);
+        }
+        let info = self.get_word_info_if_playable(&word)?;
+        Self::with_game_tiles(Cow::Borrowed(info), settings)
+    }
+
+    /// A word from the word list spelled with the game's tiles, if the game brings its own
+    fn with_game_tiles<'a>(
+        info: Cow<'a, WordInfo>,
+        settings: &GameSettings,
+    ) -> Option<Cow<'a, WordInfo>> {
+        let Some(tile_set) = &settings.tile_set else {
+            return Some(info);
+        };
+        let tiles = tile_set.tokenize(&info.word)?;
+        let mut info = info.into_owned();
+        info.score = Self::calculate_score(&tiles, tile_set);
+        info.tiles = tiles;
+        Some(Cow::Owned(info))
+    }
+
+    /// Every word playable in a game, with its allowed words added and blocked words removed
+    fn game_words<'a>(
+        &'a self,
+        settings: &'a GameSettings,
+    ) -> impl Iterator<Item = Cow<'a, WordInfo>> + 'a {
+        let allowed = settings
+            .allowed_words
+            .iter()
+            .filter_map(|(word, definition)| self.allowed_word_info(word, definition, settings));
+        self.playable_words
+            .values()
+            .filter(|info| !settings.allowed_words.contains_key(&info.word))
+            .filter_map(|info| Self::with_game_tiles(Cow::Borrowed(info), settings))
+            .chain(allowed.map(Cow::Owned))
+            .filter(|info| !settings.blocked_words.contains(&info.word))
+            .filter(|info| {
+                settings.offensive_words != OffensiveWordFilter::Block
+                    || !self.offensive_words.contains(&info.word)
+            })
+    }
+
+    /// Whether a word should be left out of the answers shown to players
+    fn is_hidden(&self, info: &WordInfo, settings: &GameSettings) -> bool {
+        settings.offensive_words != OffensiveWordFilter::Off
+            && self.offensive_words.contains(&info.word)
+    }
+
+    fn allowed_word_info(
+        &self,
+        word: &str,
+        definition: &str,
+        settings: &GameSettings,
+    ) -> Option<WordInfo> {
+        let mut info = self.unlisted_word_info(word, settings)?;
+        info.definition = definition.to_string();
+        Some(info)
+    }
+
+    /// The info for a word that is not in the word list, without a definition
+    pub(crate) fn unlisted_word_info(
+        &self,
+        word: &str,
+        settings: &GameSettings,
+    ) -> Option<WordInfo> {
+        let word = self.normalize(word);
+        let tile_set = self.tile_set(settings);
+        let tiles = tile_set.tokenize(&word)?;
+        Some(WordInfo {
+            score: Self::calculate_score(&tiles, tile_set),
+            word,
+            definition: String::new(),
+            tiles,
+        })
+    }
+
+    /// Read a list of words with one word per line
+    fn read_word_set(&self, path: &str) -> HashSet<String> {
+        let file = File::open(path).unwrap();
+        BufReader::new(file)
+            .lines()
+            .map(|line| self.normalize(&line.unwrap()))
+            .filter(|word| !word.is_empty())
+            .collect()
     }
 
     fn read_words(&self, path: &str) -> HashMap<String, WordInfo> {
@@ -124,119 +375,218 @@ impl Dictionary {
         for line in reader.lines() {
             let line = line.unwrap();
             let (word, definition) = line.split_once('\t').unwrap();
-            let word = word.to_string();
+            let word = self.normalize(word);
             let definition = definition.to_string();
-            let score = self.calculate_score(&word);
+            // Words that cannot be spelled with this language's tiles are never playable
+            let Some(tiles) = self.tokenize(&word) else {
+                continue;
+            };
+            let score = Self::calculate_score(&tiles, &self.tile_set);
             words.insert(
                 word.clone(),
                 WordInfo {
                     word,
                     score,
                     definition,
+                    tiles,
                 },
             );
         }
         words
     }
 
-    fn calculate_score(&self, s: &str) -> u32 {
-        s.chars()
-            .filter_map(|c| self.letter_scores.get(&c.to_ascii_uppercase()))
-            .sum()
+    pub(crate) fn calculate_score(tiles: &[String], tile_set: &TileSet) -> u32 {
+        tiles.iter().map(|tile| tile_set.points(tile)).sum()
     }
 
-    pub fn check_word_uses_letters(letters: &[char], answer: &str) -> bool {
-        let mut letters_left: HashMap<char, u32> = HashMap::new();
-        for letter in letters {
-            let letter_count = letters_left.entry(*letter).or_default();
-            *letter_count += 1;
+    /// The index in the letters of each tile of the word, or `None` if the letters cannot spell it
+    pub fn check_word_uses_letters(letters: &[String], tiles: &[String]) -> Option<Vec<usize>> {
+        if tiles.len() > letters.len() {
+            return None;
         }
-        for letter in answer.chars() {
-            let letter = letter.to_ascii_uppercase();
-            let entry = letters_left.entry(letter);
-            match entry {
-                std::collections::hash_map::Entry::Occupied(mut letter_count) => {
-                    let letter_count = letter_count.get_mut();
-                    if letter_count > &mut 0 {
-                        *letter_count -= 1;
-                    } else {
-                        return false;
-                    }
-                }
-                std::collections::hash_map::Entry::Vacant(_) => {
-                    return false;
-                }
-            }
-        }
-        true
+        let mut used = vec![false; letters.len()];
+        tiles
+            .iter()
+            .map(|tile| {
+                let index = (0..letters.len()).find(|&i| !used[i] && letters[i] == *tile)?;
+                used[index] = true;
+                Some(index)
+            })
+            .collect()
+    }
+
+    /// The ways to spell a word that the letters allow, starting with its usual tiles and then
+    /// trying other splits when tiles overlap, like an RE tile beside an R and an E
+    pub(crate) fn spellings_with_letters<'a>(
+        letters: &'a [String],
+        tiles: &'a [String],
+        tile_set: &'a TileSet,
+    ) -> impl Iterator<Item = Cow<'a, [String]>> + 'a {
+        let other_splits = tile_set
+            .has_multi_letter_tiles()
+            .then_some(())
+            .into_iter()
+            .flat_map(move |()| tile_set.tokenizations(&tiles.concat()))
+            .map(Cow::Owned);
+        std::iter::once(Cow::Borrowed(tiles))
+            .chain(other_splits)
+            .filter(|tiles| Self::check_word_uses_letters(letters, tiles).is_some())
     }
 
     pub async fn get_best_words(
         &self,
-        letters: &[char],
+        rack: &Rack,
         num_words: usize,
-        scoring_method: &ScoringMethod,
+        settings: &GameSettings,
     ) -> Vec<WordInfo> {
         let mut best_words: Vec<WordInfo> = Vec::new();
-
-        for (word, info) in &self.playable_words {
-            if Self::check_word_uses_letters(letters, word) {
-                let mut info = info.clone();
-                if matches!(scoring_method, ScoringMethod::Length) {
-                    info.score = info.word.len() as u32;
-                }
-                best_words.push(info);
+        for info in self.game_words(settings) {
+            if !self.is_hidden(&info, settings) {
+                best_words.extend(self.score_if_spelled(&info, rack, settings));
             }
             tokio::task::consume_budget().await;
         }
-        best_words.sort_by(|a, b| {
-            let b_score = scoring_method.score(b);
-            let a_score = scoring_method.score(a);
-            b_score.cmp(&a_score)
-        });
+        Self::keep_best_words(best_words, num_words)
+    }
+
+    /// Like `get_best_words` but without yielding, for when the words are needed right away
+    pub(crate) fn get_best_words_now(
+        &self,
+        rack: &Rack,
+        num_words: usize,
+        settings: &GameSettings,
+    ) -> Vec<WordInfo> {
+        let best_words = self
+            .game_words(settings)
+            .filter(|info| !self.is_hidden(info, settings))
+            .filter_map(|info| self.score_if_spelled(&info, rack, settings))
+            .collect();
+        Self::keep_best_words(best_words, num_words)
+    }
+
+    fn score_if_spelled(
+        &self,
+        info: &WordInfo,
+        rack: &Rack,
+        settings: &GameSettings,
+    ) -> Option<WordInfo> {
+        let tile_set = self.tile_set(settings);
+        let tiles =
+            Self::spellings_with_letters(&rack.letters, &info.tiles, tile_set).find(|tiles| {
+                rack.constraint
+                    .as_ref()
+                    .is_none_or(|constraint| constraint.is_met(&rack.letters, tiles))
+            })?;
+        let context = ScoringContext {
+            letters: &rack.letters,
+            tile_set,
+            bonuses: &rack.bonuses,
+        };
+        let mut info = info.clone();
+        info.tiles = tiles.into_owned();
+        info.score = settings.scoring_method.score(&info, &context);
+        Some(info)
+    }
+
+    fn keep_best_words(mut best_words: Vec<WordInfo>, num_words: usize) -> Vec<WordInfo> {
+        best_words.sort_by_key(|info| std::cmp::Reverse(info.score));
         best_words.truncate(num_words);
         best_words
     }
 
-    fn has_playable_word(&self, letters: &[char]) -> bool {
-        self.playable_words
-            .iter()
-            .any(|(word, _)| Self::check_word_uses_letters(letters, word))
+    /// Count the words the letters can spell, stopping once more words could not change the
+    /// rack's difficulty or whether it meets the rack constraints
+    fn rack_stats(&self, letters: &[String], settings: &GameSettings) -> RackStats {
+        let mut stats = RackStats {
+            playable_words: 0,
+            longest_word_length: None,
+        };
+        let enough_words = Difficulty::easy_word_count(letters.len());
+        let min_length = Self::min_best_word_length(letters, &settings.rack_constraints);
+        let tile_set = self.tile_set(settings);
+        for info in self.game_words(settings) {
+            if let Some(tiles) = Self::spellings_with_letters(letters, &info.tiles, tile_set).next()
+            {
+                stats.playable_words += 1;
+                stats.longest_word_length = stats.longest_word_length.max(Some(tiles.len()));
+            }
+            if stats.playable_words >= enough_words
+                && stats
+                    .longest_word_length
+                    .is_some_and(|length| length >= min_length)
+            {
+                break;
+            }
+        }
+        stats
     }
 }
 
+/// The test word list, loaded once for every test that only reads it
+#[cfg(test)]
+pub(crate) fn test_dictionary() -> &'static Dictionary {
+    static DICTIONARY: std::sync::OnceLock<Dictionary> = std::sync::OnceLock::new();
+    DICTIONARY.get_or_init(|| Dictionary::new("word-list.txt"))
+}
+
+/// A plain rack of the given letters, with no bonuses or constraint
+#[cfg(test)]
+pub(crate) fn test_rack(letters: &str) -> Rack {
+    Rack {
+        letters: letters.chars().map(String::from).collect(),
+        difficulty: Difficulty::Medium,
+        bonuses: Bonuses::default(),
+        constraint: None,
+    }
+}
+
+/// The rack most tests play on, which spells REMORSE, MORE, ORE and more
+#[cfg(test)]
+pub(crate) fn remorse_rack() -> Rack {
+    test_rack("REMORSE")
+}
+
 #[test]
 fn test_read_words() {
-    let words = Dictionary::new("word-list.txt");
+    let words = test_dictionary();
     assert_eq!(words.get_word_info_if_playable("zeugma").unwrap().score, 18);
     assert!(words.get_word_info_if_playable("notaword").is_none());
 }
 
 #[tokio::test]
 async fn test_best_words() {
-    let words = Dictionary::new("word-list.txt");
+    let words = test_dictionary();
+    let mut rack = remorse_rack();
     for value in words
-        .get_best_words(
-            &['R', 'E', 'M', 'O', 'R', 'S', 'E'],
-            5,
-            &ScoringMethod::Normal,
-        )
+        .get_best_words(&rack, 5, &GameSettings::default())
         .await
     {
         println!("{:?}", value);
     }
+    // The best words follow the round's constraint
+    rack.constraint = Some(RoundConstraint::MustStartWith {
+        letter: String::from("M"),
+    });
+    let best_words = words
+        .get_best_words(&rack, usize::MAX, &GameSettings::default())
+        .await;
+    assert!(!best_words.is_empty());
+    assert!(best_words.iter().all(|info| info.word.starts_with('M')));
 }
 
 #[tokio::test]
 async fn test_scrabble_probability() {
-    let words = Dictionary::new("word-list.txt");
+    let words = test_dictionary();
     let n = 10;
     let mut scrabbles = 0;
     let mut no_words = 0;
     for _ in 0..n {
-        let letters = words.get_random_letters(7, &HashSet::new());
-        let best_words = words.get_best_words(&letters, 1, &ScoringMethod::Normal);
-        if let Some(best_word) = best_words.await.first() {
+        let rack = words.get_random_letters(&GameSettings::default()).unwrap();
+        let letters = &rack.letters;
+        let best_words = words
+            .get_best_words(&rack, 1, &GameSettings::default())
+            .await;
+        if let Some(best_word) = best_words.first() {
             println!("best word len: {}", best_word.word.len());
             if best_word.word.len() == 7 {
                 println!("{letters:?}: {best_word:?}");
@@ -249,3 +599,120 @@ async fn test_scrabble_probability() {
     }
     println!("{scrabbles} / {n} scrabbles\n{no_words} / {n} no words");
 }
+
+#[test]
+fn test_rack_constraints() {
+    let words = test_dictionary();
+    let settings = GameSettings {
+        rack_constraints: RackConstraints {
+            min_vowels: Some(2),
+            max_vowels: Some(3),
+            max_duplicate_letters: Some(1),
+            min_best_word_length: Some(4),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for _ in 0..10 {
+        let letters = words.get_random_letters(&settings).unwrap().letters;
+        let vowels = letters.iter().filter(|c| words.vowels.contains(c)).count();
+        assert!((2..=3).contains(&vowels));
+        assert!(letters.iter().collect::<HashSet<_>>().len() >= letters.len() - 1);
+        assert!(
+            words
+                .rack_stats(&letters, &settings)
+                .longest_word_length
+                .unwrap()
+                >= 4
+        );
+    }
+    let impossible = GameSettings {
+        banned_letters: words.vowels.iter().cloned().collect(),
+        rack_constraints: RackConstraints {
+            min_vowels: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(words
+        .get_random_letters(&impossible)
+        .is_err_and(|e| matches!(e, Error::RackConstraintsUnsatisfiable)));
+    // A game in progress gets letters without the constraints rather than none at all
+    assert!(words.get_next_letters(&impossible).is_ok());
+}
+
+#[test]
+fn test_rack_difficulty() {
+    let words = test_dictionary();
+    let settings = GameSettings {
+        difficulty: Some(Difficulty::Hard),
+        ..Default::default()
+    };
+    let rack = words.get_random_letters(&settings).unwrap();
+    assert_eq!(rack.difficulty, Difficulty::Hard);
+    let stats = words.rack_stats(&rack.letters, &settings);
+    assert_eq!(
+        Difficulty::from_playable_words(stats.playable_words, rack.letters.len()),
+        Difficulty::Hard
+    );
+}
+
+#[test]
+fn test_check_word_uses_digraph_tiles() {
+    let letters: Vec<String> = ["CH", "O", "C", "O"].map(String::from).to_vec();
+    let tiles = |word: &str| word.split(' ').map(String::from).collect::<Vec<_>>();
+    assert_eq!(
+        Dictionary::check_word_uses_letters(&letters, &tiles("CH O C O")),
+        Some(vec![0, 1, 2, 3])
+    );
+    assert_eq!(
+        Dictionary::check_word_uses_letters(&letters, &tiles("C O")),
+        Some(vec![2, 1])
+    );
+    assert!(Dictionary::check_word_uses_letters(&letters, &tiles("C H O")).is_none());
+    assert!(Dictionary::check_word_uses_letters(&letters, &tiles("C O C O")).is_none());
+}
+
+#[test]
+fn test_allowed_and_blocked_words() {
+    let words = test_dictionary();
+    let mut settings = GameSettings {
+        allowed_words: HashMap::from([(String::from("ermos"), String::from("the office printer"))]),
+        blocked_words: HashSet::from([String::from("remorse")]),
+        ..Default::default()
+    };
+    words.normalize_settings(&mut settings);
+    let ermos = words.get_game_word_info("Ermos", &settings).unwrap();
+    assert_eq!(ermos.definition, "the office printer");
+    assert!(words.get_game_word_info("remorse", &settings).is_none());
+    assert!(words.get_game_word_info("more", &settings).is_some());
+    let rack = remorse_rack();
+    let best_words = words.get_best_words_now(&rack, usize::MAX, &settings);
+    assert!(best_words.iter().any(|info| info.word == "ERMOS"));
+    assert!(best_words.iter().all(|info| info.word != "REMORSE"));
+}
+
+#[test]
+fn test_offensive_word_filter() {
+    let mut words = Dictionary::new("word-list.txt");
+    words.offensive_words = HashSet::from([String::from("REMORSE")]);
+    let rack = remorse_rack();
+    let settings = |offensive_words| GameSettings {
+        offensive_words,
+        ..Default::default()
+    };
+    let shows_remorse = |settings: &GameSettings| {
+        words
+            .get_best_words_now(&rack, usize::MAX, settings)
+            .iter()
+            .any(|info| info.word == "REMORSE")
+    };
+    let off = settings(OffensiveWordFilter::Off);
+    assert!(shows_remorse(&off));
+    let hide = settings(OffensiveWordFilter::Hide);
+    assert!(!shows_remorse(&hide));
+    assert!(words.get_game_word_info("remorse", &hide).is_some());
+    let block = settings(OffensiveWordFilter::Block);
+    assert!(!shows_remorse(&block));
+    assert!(words.get_game_word_info("remorse", &block).is_none());
+}
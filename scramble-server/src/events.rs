@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bots::BotSettings,
+    challenges::{Challenge, ChallengeOutcome},
+    dictionary::WordInfo,
+    types::{
+        AnswerWithWordInfo, Error, Game, GameMode, GameSettings, Player, RejectedGuess, Result,
+        Round, Team,
+    },
+};
+
+/// A change to a game, which is recorded in the game's log and then applied
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) enum GameEvent {
+    GameCreated {
+        settings: GameSettings,
+    },
+    PlayerJoined {
+        player: Player,
+    },
+    BotAdded {
+        player: Player,
+        bot: BotSettings,
+    },
+    PlayerLeft {
+        player: Player,
+    },
+    TeamCreated {
+        team: Team,
+    },
+    TeamJoined {
+        team: Team,
+        player: Player,
+    },
+    RoundStarted {
+        round: Round,
+    },
+    /// Players started playing the current round
+    ClockStarted {
+        started_at_ms: u64,
+    },
+    /// The current round ran out of time without any answers and waits for players again
+    ClockStopped,
+    /// The current round was replaced with one using new letters
+    RoundRerolled {
+        round: Round,
+    },
+    RerollVoted {
+        player: Player,
+    },
+    BestAnswersFound {
+        round: usize,
+        best_answers: Vec<WordInfo>,
+    },
+    HintGiven {
+        player: Player,
+    },
+    AnswerAccepted {
+        answer: AnswerWithWordInfo,
+    },
+    GuessRejected {
+        guess: RejectedGuess,
+    },
+    /// The current round's answers after their scores were adjusted
+    RoundScored {
+        answers: Vec<AnswerWithWordInfo>,
+        word_lists: HashMap<Player, Vec<AnswerWithWordInfo>>,
+    },
+    PlayerEliminated {
+        player: Player,
+    },
+    ChallengeRaised {
+        challenge: Challenge,
+    },
+    ChallengeVoted {
+        id: usize,
+        player: Player,
+        agree: bool,
+    },
+    /// A challenge was decided, along with the challenged answer's new score if it was upheld
+    ChallengeResolved {
+        id: usize,
+        outcome: ChallengeOutcome,
+        answer: Option<AnswerWithWordInfo>,
+        house_word: Option<String>,
+    },
+}
+
+impl Game {
+    /// Rebuild a game by applying every event in its log in order
+    pub(crate) fn from_events(events: Vec<GameEvent>) -> Result<Self> {
+        if events.is_empty() {
+            return Err(Error::InvalidExport);
+        }
+        let mut game = Game::default();
+        for event in events {
+            game.check_event(&event)?;
+            game.apply(event);
+        }
+        if game.rounds.is_empty() {
+            return Err(Error::InvalidExport);
+        }
+        Ok(game)
+    }
+
+    /// Check that an event from outside the server can be applied to the game as it is
+    fn check_event(&self, event: &GameEvent) -> Result<()> {
+        let is_valid = match event {
+            // The settings come first and only once
+            GameEvent::GameCreated { settings } => self.events.is_empty() && settings.is_valid(),
+            _ if self.events.is_empty() => false,
+            // A game always has a round for players to join and answer in
+            GameEvent::RoundStarted { .. } => true,
+            _ if self.rounds.is_empty() => false,
+            GameEvent::BestAnswersFound { round, .. } => *round < self.rounds.len(),
+            GameEvent::ChallengeRaised { challenge } => challenge.round < self.rounds.len(),
+            GameEvent::ChallengeVoted { id, .. } | GameEvent::ChallengeResolved { id, .. } => {
+                *id < self.challenges.len()
+            }
+            GameEvent::PlayerJoined { .. }
+            | GameEvent::BotAdded { .. }
+            | GameEvent::PlayerLeft { .. }
+            | GameEvent::TeamCreated { .. }
+            | GameEvent::TeamJoined { .. }
+            | GameEvent::RoundRerolled { .. }
+            | GameEvent::ClockStarted { .. }
+            | GameEvent::ClockStopped
+            | GameEvent::RerollVoted { .. }
+            | GameEvent::HintGiven { .. }
+            | GameEvent::AnswerAccepted { .. }
+            | GameEvent::GuessRejected { .. }
+            | GameEvent::RoundScored { .. }
+            | GameEvent::PlayerEliminated { .. } => true,
+        };
+        if is_valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidExport)
+        }
+    }
+
+    /// Change the game as the event describes and append the event to the log
+    pub(crate) fn apply(&mut self, event: GameEvent) {
+        match event.clone() {
+            GameEvent::GameCreated { settings } => self.settings = settings,
+            GameEvent::PlayerJoined { player } => {
+                self.players.insert(player);
+            }
+            GameEvent::BotAdded { player, bot } => {
+                self.players.insert(player.clone());
+                self.bots.insert(player, bot);
+            }
+            GameEvent::PlayerLeft { player } => {
+                self.players.remove(&player);
+                self.bots.remove(&player);
+                for players in self.teams.values_mut() {
+                    players.remove(&player);
+                }
+            }
+            GameEvent::TeamCreated { team } => {
+                self.teams.insert(team, Default::default());
+            }
+            GameEvent::TeamJoined { team, player } => {
+                // A player can only be on one team at a time
+                for players in self.teams.values_mut() {
+                    players.remove(&player);
+                }
+                self.teams.entry(team).or_default().insert(player);
+            }
+            GameEvent::RoundStarted { round } => self.rounds.push(round),
+            GameEvent::ClockStarted { started_at_ms } => {
+                let time_limit_ms = match self.settings.mode {
+                    GameMode::SingleWord => None,
+                    GameMode::MultiWord => {
+                        Some(self.settings.round_time_limit_seconds.saturating_mul(1000))
+                    }
+                };
+                let round = self.current_round_mut();
+                round.started_at_ms = Some(started_at_ms);
+                round.deadline_ms =
+                    time_limit_ms.map(|time_limit_ms| started_at_ms.saturating_add(time_limit_ms));
+            }
+            GameEvent::ClockStopped => {
+                let round = self.current_round_mut();
+                round.started_at_ms = None;
+                round.deadline_ms = None;
+            }
+            GameEvent::RoundRerolled { round } => {
+                self.rerolls_used += 1;
+                self.rounds.pop();
+                self.rounds.push(round);
+            }
+            GameEvent::RerollVoted { player } => {
+                self.current_round_mut().reroll_votes.insert(player);
+            }
+            GameEvent::BestAnswersFound {
+                round,
+                best_answers,
+            } => {
+                if let Some(round) = self.rounds.get_mut(round) {
+                    round.best_answers = best_answers;
+                }
+            }
+            GameEvent::HintGiven { player } => {
+                *self
+                    .current_round_mut()
+                    .hints_used
+                    .entry(player)
+                    .or_default() += 1;
+            }
+            GameEvent::AnswerAccepted { answer } => {
+                let multi_word = self.settings.mode == GameMode::MultiWord;
+                let round = self.current_round_mut();
+                if multi_word {
+                    let player = answer.player.clone();
+                    round.word_lists.entry(player).or_default().push(answer);
+                } else {
+                    round.answers.push(answer);
+                }
+            }
+            GameEvent::GuessRejected { guess } => self.reject_guess(guess),
+            GameEvent::RoundScored {
+                answers,
+                word_lists,
+            } => {
+                let round = self.current_round_mut();
+                round.answers = answers;
+                round.word_lists = word_lists;
+                round.scored = true;
+            }
+            GameEvent::PlayerEliminated { player } => self.eliminated.push(player),
+            GameEvent::ChallengeRaised { challenge } => self.challenges.push(challenge),
+            GameEvent::ChallengeVoted { id, player, agree } => {
+                if let Some(challenge) = self.challenges.get_mut(id) {
+                    challenge.votes.insert(player, agree);
+                }
+            }
+            GameEvent::ChallengeResolved {
+                id,
+                outcome,
+                answer,
+                house_word,
+            } => {
+                let Some(challenge) = self.challenges.get_mut(id) else {
+                    return;
+                };
+                challenge.outcome = Some(outcome);
+                let round = self.rounds.get_mut(challenge.round);
+                if let (Some(round), Some(answer)) = (round, answer) {
+                    let old_answer = round
+                        .all_answers_mut()
+                        .find(|a| a.player == answer.player && a.answer == answer.answer);
+                    if let Some(old_answer) = old_answer {
+                        *old_answer = answer;
+                    }
+                }
+                self.house_words.extend(house_word);
+            }
+        }
+        self.events.push(event);
+    }
+
+    fn reject_guess(&mut self, guess: RejectedGuess) {
+        let number_of_guesses = self.settings.number_of_guesses;
+        let multi_word = self.settings.mode == GameMode::MultiWord;
+        let round = self.current_round_mut();
+        round.rejected_guesses.push(guess.clone());
+        // Wrong guesses never forfeit a multi-word round
+        if multi_word {
+            return;
+        }
+        let guesses_used = round.guesses_used.entry(guess.player.clone()).or_default();
+        *guesses_used += 1;
+        if *guesses_used == number_of_guesses {
+            round.answers.push(AnswerWithWordInfo {
+                player: guess.player,
+                answer: guess.guess,
+                score: 0,
+                definition: String::from(""),
+                word_score: 0,
+                adjustments: Vec::new(),
+                answer_time_ms: guess.answer_time_ms,
+            });
+        }
+    }
+}
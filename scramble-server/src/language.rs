@@ -0,0 +1,105 @@
+use std::{collections::HashMap, fs::File, io::BufReader};
+
+use serde::Deserialize;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    dictionary::Dictionary,
+    tiles::TileSet,
+    types::{Error, Result},
+};
+
+/// The language games are played in when their settings do not choose one
+pub(crate) const DEFAULT_LANGUAGE: &str = "English";
+
+/// The rules for turning typed words into the form used by the word list and tiles
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Normalization {
+    /// Replacements applied in order after uppercasing, such as mapping accented
+    /// letters to the plain letter when the tiles ignore accents
+    replacements: Vec<(String, String)>,
+}
+
+impl Normalization {
+    pub(crate) fn normalize(&self, s: &str) -> String {
+        let mut normalized = s.trim().nfc().collect::<String>().to_uppercase();
+        for (from, to) in &self.replacements {
+            normalized = normalized.replace(from, to);
+        }
+        normalized
+    }
+}
+
+/// Everything needed to play in a language
+#[derive(Deserialize)]
+pub(crate) struct LanguagePack {
+    /// The name games use to select the language
+    pub(crate) name: String,
+    /// The path to a tab separated list of words and definitions
+    pub(crate) word_list: String,
+    /// The tiles to draw letters from
+    #[serde(default)]
+    pub(crate) tile_set: TileSet,
+    /// How words are normalized before being matched against tiles
+    #[serde(default)]
+    pub(crate) normalization: Normalization,
+    /// The tiles that count as vowels for the rack constraints
+    #[serde(default = "default_vowels")]
+    pub(crate) vowels: Vec<String>,
+    /// The path to a list of words to flag as offensive, one per line
+    #[serde(default)]
+    pub(crate) offensive_words: Option<String>,
+}
+
+fn default_vowels() -> Vec<String> {
+    ["A", "E", "I", "O", "U"].map(String::from).to_vec()
+}
+
+impl LanguagePack {
+    pub(crate) fn english(word_list: &str, tile_set: TileSet) -> Self {
+        Self {
+            name: DEFAULT_LANGUAGE.to_string(),
+            word_list: word_list.to_string(),
+            tile_set,
+            normalization: Normalization::default(),
+            vowels: default_vowels(),
+            offensive_words: None,
+        }
+    }
+
+    /// Read a JSON list of language packs
+    pub(crate) fn from_file(path: &str) -> Vec<Self> {
+        let file = File::open(path).unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+}
+
+/// The dictionaries for every language the server supports
+pub(crate) struct Languages(HashMap<String, Dictionary>);
+
+impl Languages {
+    pub(crate) fn new(packs: Vec<LanguagePack>) -> Self {
+        Self(
+            packs
+                .into_iter()
+                .map(|pack| (pack.name.clone(), Dictionary::from_language_pack(pack)))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn get(&self, language: &str) -> Result<&Dictionary> {
+        self.0.get(language).ok_or(Error::LanguageNotFound)
+    }
+}
+
+#[test]
+fn test_normalization() {
+    let normalization: Normalization =
+        serde_json::from_str(r#"{"replacements": [["É", "E"], ["È", "E"]]}"#).unwrap();
+    assert_eq!(normalization.normalize("élève"), "ELEVE");
+    // A decomposed accent is composed before replacing
+    assert_eq!(normalization.normalize("e\u{301}te\u{301}"), "ETE");
+    assert_eq!(Normalization::default().normalize(" señor "), "SEÑOR");
+    assert_eq!(Normalization::default().normalize("straße"), "STRASSE");
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Game, Player};
+
+/// The length of a day, which the leaderboard for today starts at the beginning of
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// How far back results count towards the weekly leaderboard, and so how long they are kept
+const WEEK_MS: u64 = 7 * DAY_MS;
+
+/// The number of players on a page of a leaderboard when no page size is given
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// The most players a page of a leaderboard can show
+const MAX_PAGE_SIZE: usize = 100;
+
+/// Which results a leaderboard counts
+#[derive(Clone, Copy, Debug, FromFormField, PartialEq, Eq)]
+pub(crate) enum Period {
+    AllTime,
+    /// Rounds finished in the last seven days
+    Weekly,
+    /// Rounds of any game finished since midnight UTC, which is a calendar day rather than a
+    /// shared puzzle, since every game draws its own letters
+    Today,
+}
+
+impl Period {
+    /// The earliest time a round can have finished to count, in milliseconds since the Unix epoch
+    fn start_ms(self, now_ms: u64) -> u64 {
+        match self {
+            Self::AllTime => 0,
+            Self::Weekly => now_ms.saturating_sub(WEEK_MS),
+            Self::Today => now_ms - now_ms % DAY_MS,
+        }
+    }
+}
+
+/// What players on a leaderboard are ranked by, highest first
+#[derive(Clone, Copy, Debug, FromFormField, PartialEq, Eq)]
+pub(crate) enum RankBy {
+    /// The number of games won
+    Wins,
+    TotalScore,
+    AveragePercentOfBest,
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct LeaderboardQuery {
+    /// All time when not given
+    pub(crate) period: Option<Period>,
+    /// Only count games played by this club
+    pub(crate) club: Option<String>,
+    /// Wins when not given
+    pub(crate) rank_by: Option<RankBy>,
+    /// The page to show, starting from zero
+    pub(crate) page: Option<usize>,
+    pub(crate) page_size: Option<usize>,
+}
+
+/// How one player did in one completed round
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct RoundResult {
+    player: Player,
+    /// The game and round the result is from
+    game_id: String,
+    round: usize,
+    /// The club of the game the round was played in
+    club: Option<String>,
+    /// When the round was scored, in milliseconds since the Unix epoch
+    finished_at_ms: u64,
+    score: u32,
+    won: bool,
+    percent_of_best: Option<u32>,
+}
+
+/// How one player did in one finished game
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct GameResult {
+    player: Player,
+    /// The club of the game
+    club: Option<String>,
+    /// When the game finished, in milliseconds since the Unix epoch
+    finished_at_ms: u64,
+    won: bool,
+}
+
+/// A player's results added up, across every club or in one club
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct Totals {
+    /// The club the results are from, when they are kept by club
+    club: Option<String>,
+    games_won: u32,
+    rounds_won: u32,
+    total_score: u32,
+    /// The number of rounds where the player's best word was compared to the round's best word
+    rounds_with_best: u32,
+    total_percent_of_best: u64,
+}
+
+impl Totals {
+    fn add(&mut self, other: &Totals) {
+        self.games_won += other.games_won;
+        self.rounds_won += other.rounds_won;
+        self.total_score += other.total_score;
+        self.rounds_with_best += other.rounds_with_best;
+        self.total_percent_of_best += other.total_percent_of_best;
+    }
+
+    fn add_round(&mut self, result: &RoundResult) {
+        self.rounds_won += u32::from(result.won);
+        self.total_score += result.score;
+        self.add_percent_of_best(result.percent_of_best);
+    }
+
+    fn add_percent_of_best(&mut self, percent_of_best: Option<u32>) {
+        if let Some(percent) = percent_of_best {
+            self.rounds_with_best += 1;
+            self.total_percent_of_best += u64::from(percent);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct LeaderboardEntry {
+    /// The player's position on the leaderboard, starting from one
+    pub(crate) rank: usize,
+    pub(crate) player: Player,
+    pub(crate) games_won: u32,
+    pub(crate) rounds_won: u32,
+    pub(crate) total_score: u32,
+    pub(crate) average_percent_of_best: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Leaderboard {
+    /// The number of players on the whole leaderboard
+    pub(crate) total_players: usize,
+    /// The players on the requested page
+    pub(crate) entries: Vec<LeaderboardEntry>,
+}
+
+/// The results leaderboards are built from
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct Leaderboards {
+    /// The results of the rounds completed in the last week
+    rounds: Vec<RoundResult>,
+    /// The results of the games finished in the last week
+    games: Vec<GameResult>,
+    /// Every player's results of all time, kept by club so that older results can be dropped
+    all_time: HashMap<Player, Vec<Totals>>,
+}
+
+impl Leaderboards {
+    /// The all time results of a player in a club
+    fn all_time_totals(&mut self, player: &Player, club: &Option<String>) -> &mut Totals {
+        let totals = self.all_time.entry(player.clone()).or_default();
+        let index = match totals.iter().position(|totals| &totals.club == club) {
+            Some(index) => index,
+            None => {
+                totals.push(Totals {
+                    club: club.clone(),
+                    ..Default::default()
+                });
+                totals.len() - 1
+            }
+        };
+        &mut totals[index]
+    }
+
+    fn add_round_result(&mut self, result: RoundResult) {
+        self.all_time_totals(&result.player, &result.club)
+            .add_round(&result);
+        let start_ms = result.finished_at_ms.saturating_sub(WEEK_MS);
+        self.rounds.retain(|round| round.finished_at_ms >= start_ms);
+        self.rounds.push(result);
+    }
+
+    fn add_game_result(&mut self, result: GameResult) {
+        self.all_time_totals(&result.player, &result.club).games_won += u32::from(result.won);
+        let start_ms = result.finished_at_ms.saturating_sub(WEEK_MS);
+        self.games.retain(|game| game.finished_at_ms >= start_ms);
+        self.games.push(result);
+    }
+
+    /// Add the results of the game's current round, which should have just been scored
+    pub(crate) fn record_round(&mut self, game_id: &str, game: &Game, finished_at_ms: u64) {
+        let round = game.current_round();
+        let round_scores = game.round_scores(round);
+        let winning_score = round_scores.values().copied().max().unwrap_or(0);
+        for (player, score) in round_scores {
+            if game.bots.contains_key(player) {
+                continue;
+            }
+            self.add_round_result(RoundResult {
+                player: player.clone(),
+                game_id: game_id.to_string(),
+                round: game.rounds.len() - 1,
+                club: game.settings.club.clone(),
+                finished_at_ms,
+                score,
+                won: score == winning_score && score > 0,
+                percent_of_best: round.percent_of_best(player),
+            });
+        }
+    }
+
+    /// Add how close each player came to a recorded round's best word, once it is known
+    pub(crate) fn record_percent_of_best(&mut self, game_id: &str, game: &Game, round: usize) {
+        let Some(round_ref) = game.rounds.get(round) else {
+            return;
+        };
+        let mut found = Vec::new();
+        for result in self
+            .rounds
+            .iter_mut()
+            .filter(|result| result.game_id == game_id && result.round == round)
+        {
+            if result.percent_of_best.is_none() {
+                result.percent_of_best = round_ref.percent_of_best(&result.player);
+                found.push((
+                    result.player.clone(),
+                    result.club.clone(),
+                    result.percent_of_best,
+                ));
+            }
+        }
+        for (player, club, percent_of_best) in found {
+            self.all_time_totals(&player, &club)
+                .add_percent_of_best(percent_of_best);
+        }
+    }
+
+    /// Add whether each player won a game that has just finished, by its scored rounds
+    pub(crate) fn record_game(&mut self, game: &Game, finished_at_ms: u64) {
+        if !game.rounds.iter().any(|round| round.scored) {
+            return;
+        }
+        let scores = game.get_final_score();
+        let winning_score = scores.players.values().copied().max().unwrap_or(0);
+        for (player, score) in &scores.players {
+            if game.bots.contains_key(player) {
+                continue;
+            }
+            // Elimination games are won by the last player standing, others by the top score
+            let won = match scores.ranking.first() {
+                Some(winner) => winner == player,
+                None => *score == winning_score && *score > 0,
+            };
+            self.add_game_result(GameResult {
+                player: player.clone(),
+                club: game.settings.club.clone(),
+                finished_at_ms,
+                won,
+            });
+        }
+    }
+
+    pub(crate) fn get(&self, query: &LeaderboardQuery, now_ms: u64) -> Leaderboard {
+        let in_club = |club: &Option<String>| {
+            query
+                .club
+                .as_ref()
+                .is_none_or(|query_club| club.as_ref() == Some(query_club))
+        };
+        let mut totals: HashMap<&Player, Totals> = HashMap::new();
+        match query.period.unwrap_or(Period::AllTime) {
+            Period::AllTime => {
+                for (player, club_totals) in &self.all_time {
+                    for club_totals in club_totals.iter().filter(|totals| in_club(&totals.club)) {
+                        totals.entry(player).or_default().add(club_totals);
+                    }
+                }
+            }
+            period => {
+                let start_ms = period.start_ms(now_ms);
+                let counts = |finished_at_ms: u64, club: &Option<String>| {
+                    finished_at_ms >= start_ms && in_club(club)
+                };
+                for result in self
+                    .games
+                    .iter()
+                    .filter(|result| counts(result.finished_at_ms, &result.club))
+                {
+                    totals.entry(&result.player).or_default().games_won += u32::from(result.won);
+                }
+                for result in self
+                    .rounds
+                    .iter()
+                    .filter(|result| counts(result.finished_at_ms, &result.club))
+                {
+                    totals.entry(&result.player).or_default().add_round(result);
+                }
+            }
+        }
+        let mut entries: Vec<LeaderboardEntry> = totals
+            .into_iter()
+            .map(|(player, totals)| LeaderboardEntry {
+                rank: 0,
+                player: player.clone(),
+                games_won: totals.games_won,
+                rounds_won: totals.rounds_won,
+                total_score: totals.total_score,
+                average_percent_of_best: if totals.rounds_with_best == 0 {
+                    0.0
+                } else {
+                    totals.total_percent_of_best as f64 / totals.rounds_with_best as f64
+                },
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            let order = match query.rank_by.unwrap_or(RankBy::Wins) {
+                RankBy::Wins => b.games_won.cmp(&a.games_won),
+                RankBy::TotalScore => b.total_score.cmp(&a.total_score),
+                RankBy::AveragePercentOfBest => b
+                    .average_percent_of_best
+                    .total_cmp(&a.average_percent_of_best),
+            };
+            order.then_with(|| a.player.cmp(&b.player))
+        });
+        for (i, entry) in entries.iter_mut().enumerate() {
+            entry.rank = i + 1;
+        }
+        let total_players = entries.len();
+        let page_size = query
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let page = query.page.unwrap_or(0);
+        let entries = entries
+            .into_iter()
+            .skip(page.saturating_mul(page_size))
+            .take(page_size)
+            .collect();
+        Leaderboard {
+            total_players,
+            entries,
+        }
+    }
+}
+
+#[test]
+fn test_leaderboards() {
+    let result = |player: &str, club: Option<&str>, finished_at_ms, score, won| RoundResult {
+        player: String::from(player),
+        game_id: String::from("game"),
+        round: 0,
+        club: club.map(String::from),
+        finished_at_ms,
+        score,
+        won,
+        percent_of_best: Some(score * 10),
+    };
+    let game_result = |player: &str, finished_at_ms, won| GameResult {
+        player: String::from(player),
+        club: Some(String::from("club")),
+        finished_at_ms,
+        won,
+    };
+    let mut leaderboards = Leaderboards::default();
+    let rounds = [
+        result("a", None, 0, 9, true),
+        result("b", None, 0, 6, false),
+        result("a", Some("club"), WEEK_MS, 3, false),
+        result("b", Some("club"), WEEK_MS, 6, true),
+        result("c", Some("club"), WEEK_MS, 6, true),
+        result("c", Some("club"), WEEK_MS + DAY_MS, 6, true),
+    ];
+    for result in rounds {
+        leaderboards.add_round_result(result);
+    }
+    // C won more rounds than B, but B won the game
+    for (player, won) in [("a", false), ("b", true), ("c", false)] {
+        leaderboards.add_game_result(game_result(player, WEEK_MS, won));
+    }
+    // Results older than a week are dropped once they only count all time
+    assert_eq!(leaderboards.rounds.len(), 4);
+    let query = |period, club: Option<&str>, rank_by, page, page_size| LeaderboardQuery {
+        period: Some(period),
+        club: club.map(String::from),
+        rank_by: Some(rank_by),
+        page: Some(page),
+        page_size: Some(page_size),
+    };
+    let players = |leaderboard: Leaderboard| -> Vec<Player> {
+        leaderboard.entries.into_iter().map(|e| e.player).collect()
+    };
+    let now_ms = WEEK_MS + DAY_MS + 1;
+
+    let all_time = leaderboards.get(
+        &query(Period::AllTime, None, RankBy::TotalScore, 0, 20),
+        now_ms,
+    );
+    assert_eq!(all_time.total_players, 3);
+    assert_eq!(all_time.entries[0].player, "a");
+    assert_eq!(all_time.entries[0].total_score, 12);
+    assert_eq!(all_time.entries[0].average_percent_of_best, 60.0);
+    let weekly = leaderboards.get(&query(Period::Weekly, None, RankBy::Wins, 0, 20), now_ms);
+    assert_eq!(weekly.entries[2].rounds_won, 2);
+    assert_eq!(players(weekly), ["b", "a", "c"]);
+    let today = leaderboards.get(
+        &query(Period::Today, None, RankBy::TotalScore, 0, 20),
+        now_ms,
+    );
+    assert_eq!(players(today), ["c"]);
+    let club = leaderboards.get(
+        &query(Period::AllTime, Some("club"), RankBy::Wins, 1, 2),
+        now_ms,
+    );
+    assert_eq!(club.total_players, 3);
+    assert_eq!(club.entries[0].rank, 3);
+    assert_eq!(players(club), ["c"]);
+}
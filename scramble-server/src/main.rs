@@ -1,16 +1,33 @@
+mod bots;
+mod challenges;
 mod dictionary;
+mod events;
+mod language;
+mod leaderboards;
+mod profiles;
+mod ratings;
+mod scoring;
+mod tiles;
 mod types;
 
-use std::collections::HashMap;
 use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use dictionary::Dictionary;
+use dictionary::{Dictionary, Rack};
+use language::{LanguagePack, Languages};
+use leaderboards::{Leaderboard, LeaderboardQuery};
+use profiles::Profile;
 use rocket::config::LogLevel;
+use rocket::fairing::AdHoc;
 use rocket::http::Method;
 use rocket_cors::{AllowedOrigins, CorsOptions};
 use structopt::StructOpt;
-use types::{CreateGameData, Game, Games, Player, ScoringMethod};
+use tiles::TileSet;
+use types::{
+    write_stats, AddBotData, ChallengeData, CreateGameData, Game, GameExport, GameListing,
+    GameSettings, Games, Hint, Player, RoundState, Scores, VoteData,
+};
 
 use crate::types::{Answer, PlayerData, Result};
 use rocket::serde::json::Json;
@@ -20,47 +37,75 @@ use rocket::{tokio, Config, State};
 extern crate rocket;
 
 #[put("/game/<game_id>", data = "<create_game_data>")]
-fn create_game(
+async fn create_game(
     game_id: &str,
     mut create_game_data: Json<CreateGameData>,
     games_state: &State<Arc<Mutex<Games>>>,
-    dictionary: &State<Arc<Dictionary>>,
+    languages: &State<Arc<Languages>>,
 ) -> Result<()> {
+    let dictionary = languages.get(&create_game_data.settings.language)?;
+    dictionary.normalize_settings(&mut create_game_data.settings);
+    let rack = draw_letters(
+        languages.inner().clone(),
+        create_game_data.settings.clone(),
+        Dictionary::get_random_letters,
+    )
+    .await?;
     let mut games = games_state.lock().unwrap();
-    create_game_data.settings.banned_letters = create_game_data
-        .settings
-        .banned_letters
-        .iter()
-        .map(char::to_ascii_uppercase)
-        .collect();
-    let tiles = dictionary.get_random_letters(
-        create_game_data.settings.number_of_tiles as usize,
-        &create_game_data.settings.banned_letters,
-    );
     games.create(
         game_id.to_string(),
         create_game_data.player.clone(),
         create_game_data.settings.clone(),
-        tiles.clone(),
+        rack,
     )?;
-    let dictionary_clone = dictionary.inner().clone();
-    let scoring_method = create_game_data.settings.scoring_method.clone();
-    let games_state_clone = games_state.inner().clone();
-    let game_id_clone = game_id.to_string();
-    tokio::spawn(async move {
-        get_best_words_for_round(
-            dictionary_clone,
-            tiles,
-            scoring_method,
-            games_state_clone,
-            game_id_clone,
-            0,
-        )
-        .await
-    });
+    let game = games.get(game_id)?;
+    start_round(game_id, game, languages, games_state);
     Ok(())
 }
 
+#[get("/games")]
+fn list_games(games: &State<Arc<Mutex<Games>>>) -> Json<Vec<GameListing>> {
+    let games = games.lock().unwrap();
+    Json(games.public_games())
+}
+
+/// Join an open public game with compatible settings, or create one, returning the game ID
+#[post("/quick-match", data = "<create_game_data>")]
+async fn quick_match(
+    mut create_game_data: Json<CreateGameData>,
+    games_state: &State<Arc<Mutex<Games>>>,
+    languages: &State<Arc<Languages>>,
+) -> Result<Json<String>> {
+    let dictionary = languages.get(&create_game_data.settings.language)?;
+    dictionary.normalize_settings(&mut create_game_data.settings);
+    create_game_data.settings.public = true;
+    let CreateGameData { player, settings } = create_game_data.into_inner();
+    {
+        let mut games = games_state.lock().unwrap();
+        if let Some(game_id) = games.find_open_game(&player, &settings) {
+            games.get_playable(&game_id)?.add_player(player)?;
+            return Ok(Json(game_id));
+        }
+    }
+    let rack = draw_letters(
+        languages.inner().clone(),
+        settings.clone(),
+        Dictionary::get_random_letters,
+    )
+    .await?;
+    let mut games = games_state.lock().unwrap();
+    // Another quick match may have opened a game to join while the letters were drawn
+    if let Some(game_id) = games.find_open_game(&player, &settings) {
+        games.get_playable(&game_id)?.add_player(player)?;
+        return Ok(Json(game_id));
+    }
+    let game_id = games.unused_game_id();
+    games.create(game_id.clone(), player, settings, rack)?;
+    let game = games.get(&game_id)?;
+    start_round(&game_id, game, languages, games_state);
+    Ok(Json(game_id))
+}
+
 #[post("/game/<game_id>", data = "<player>")]
 fn join_game(
     game_id: &str,
@@ -68,7 +113,7 @@ fn join_game(
     games: &State<Arc<Mutex<Games>>>,
 ) -> Result<()> {
     let mut games = games.lock().unwrap();
-    let game = games.get(game_id)?;
+    let game = games.get_playable(game_id)?;
     game.add_player(player.into_inner().player)
 }
 
@@ -84,36 +129,158 @@ fn answer(
     game_id: &str,
     answer: Json<Answer>,
     games_state: &State<Arc<Mutex<Games>>>,
-    dictionary: &State<Arc<Dictionary>>,
+    languages: &State<Arc<Languages>>,
 ) -> Result<()> {
     let mut games = games_state.lock().unwrap();
-    let game = games.get(game_id)?;
-    game.answer(answer.into_inner(), dictionary)?;
-    let tiles = dictionary.get_random_letters(
-        game.settings.number_of_tiles as usize,
-        &game.settings.banned_letters,
-    );
-    if game.add_round_if_complete(tiles.clone()) {
-        let dictionary_clone = dictionary.inner().clone();
-        let scoring_method = game.settings.scoring_method.clone();
-        let games_state_clone = games_state.inner().clone();
-        let game_id_clone = game_id.to_string();
-        let i = game.rounds.len() - 1;
-        tokio::spawn(async move {
-            get_best_words_for_round(
-                dictionary_clone,
-                tiles,
-                scoring_method,
-                games_state_clone,
-                game_id_clone,
-                i,
-            )
-            .await
-        });
+    submit_answer(
+        game_id,
+        &mut games,
+        answer.into_inner(),
+        languages,
+        games_state,
+    )
+}
+
+#[post("/game/<game_id>/bot", data = "<add_bot_data>")]
+fn add_bot(
+    game_id: &str,
+    add_bot_data: Json<AddBotData>,
+    games_state: &State<Arc<Mutex<Games>>>,
+    languages: &State<Arc<Languages>>,
+) -> Result<()> {
+    let mut games = games_state.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    let AddBotData { player, bot } = add_bot_data.into_inner();
+    game.add_bot(player.clone(), bot)?;
+    // Otherwise the bot answers along with the others once the clock starts
+    if game.clock_started() {
+        spawn_bot_answer(game_id, game, player, languages, games_state);
+    }
+    Ok(())
+}
+
+/// Start the clock on the current round once a player is ready
+#[post("/game/<game_id>/start", data = "<player>")]
+fn start(
+    game_id: &str,
+    player: Json<PlayerData>,
+    games_state: &State<Arc<Mutex<Games>>>,
+    languages: &State<Arc<Languages>>,
+) -> Result<()> {
+    let mut games = games_state.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    if game.start(player.into_inner().player)? {
+        play_round(game_id, game, languages, games_state);
+    }
+    Ok(())
+}
+
+#[post("/game/<game_id>/reroll", data = "<player>")]
+async fn vote_reroll(
+    game_id: &str,
+    player: Json<PlayerData>,
+    games_state: &State<Arc<Mutex<Games>>>,
+    languages: &State<Arc<Languages>>,
+) -> Result<()> {
+    let (settings, letters) = {
+        let mut games = games_state.lock().unwrap();
+        let game = games.get_playable(game_id)?;
+        if !game.vote_reroll(player.into_inner().player)? {
+            return Ok(());
+        }
+        (game.settings.clone(), game.current_round().letters.clone())
+    };
+    let rack = draw_letters(
+        languages.inner().clone(),
+        settings,
+        Dictionary::get_random_letters,
+    )
+    .await?;
+    let mut games = games_state.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    // Another vote may have replaced the letters while these were drawn
+    if game.current_round().letters != letters || game.current_round_state() != RoundState::Start {
+        return Ok(());
     }
+    game.reroll(rack);
+    start_round(game_id, game, languages, games_state);
     Ok(())
 }
 
+#[post("/game/<game_id>/hint", data = "<player>")]
+fn hint(
+    game_id: &str,
+    player: Json<PlayerData>,
+    games: &State<Arc<Mutex<Games>>>,
+) -> Result<Json<Hint>> {
+    let mut games = games.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    Ok(Json(game.hint(player.into_inner().player)?))
+}
+
+#[post("/game/<game_id>/challenge", data = "<challenge>")]
+fn challenge(
+    game_id: &str,
+    challenge: Json<ChallengeData>,
+    games: &State<Arc<Mutex<Games>>>,
+    languages: &State<Arc<Languages>>,
+) -> Result<Json<usize>> {
+    let mut games = games.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    let dictionary = languages.get(&game.settings.language)?;
+    Ok(Json(game.challenge(challenge.into_inner(), dictionary)?))
+}
+
+#[post("/game/<game_id>/challenge/<challenge_id>/vote", data = "<vote>")]
+fn vote_on_challenge(
+    game_id: &str,
+    challenge_id: usize,
+    vote: Json<VoteData>,
+    games: &State<Arc<Mutex<Games>>>,
+    languages: &State<Arc<Languages>>,
+) -> Result<()> {
+    let mut games = games.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    let dictionary = languages.get(&game.settings.language)?;
+    game.vote_on_challenge(challenge_id, vote.into_inner(), dictionary)
+}
+
+#[put("/game/<game_id>/team/<team>")]
+fn create_team(game_id: &str, team: &str, games: &State<Arc<Mutex<Games>>>) -> Result<()> {
+    let mut games = games.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    game.create_team(team.to_string())
+}
+
+#[post("/game/<game_id>/team/<team>", data = "<player>")]
+fn join_team(
+    game_id: &str,
+    team: &str,
+    player: Json<PlayerData>,
+    games: &State<Arc<Mutex<Games>>>,
+) -> Result<()> {
+    let mut games = games.lock().unwrap();
+    let game = games.get_playable(game_id)?;
+    game.join_team(team.to_string(), player.into_inner().player)
+}
+
+#[get("/game/<game_id>/export")]
+fn export_game(game_id: &str, games: &State<Arc<Mutex<Games>>>) -> Result<Json<GameExport>> {
+    let mut games = games.lock().unwrap();
+    let game = games.get(game_id)?;
+    Ok(Json(GameExport::new(game)))
+}
+
+#[put("/game/<game_id>/import", data = "<export>")]
+fn import_game(
+    game_id: &str,
+    export: Json<GameExport>,
+    games: &State<Arc<Mutex<Games>>>,
+) -> Result<()> {
+    let mut games = games.lock().unwrap();
+    games.import(game_id.to_string(), export.into_inner())
+}
+
 #[delete("/game/<game_id>/exit", data = "<player>")]
 fn exit_game(
     game_id: &str,
@@ -121,7 +288,7 @@ fn exit_game(
     games: &State<Arc<Mutex<Games>>>,
 ) -> Result<()> {
     let mut games = games.lock().unwrap();
-    let game = games.get(game_id)?;
+    let game = games.get_playable(game_id)?;
     game.remove_player(player.into_inner().player)
 }
 
@@ -132,32 +299,322 @@ fn delete_game(game_id: &str, games: &State<Arc<Mutex<Games>>>) {
 }
 
 #[get("/game/<game_id>/score")]
-fn get_score(
+fn get_score(game_id: &str, games: &State<Arc<Mutex<Games>>>) -> Result<Json<Scores>> {
+    let mut games = games.lock().unwrap();
+    let game = games.get(game_id)?;
+    Ok(Json(game.get_score()))
+}
+
+#[get("/player/<player>")]
+fn profile(player: &str, games: &State<Arc<Mutex<Games>>>) -> Result<Json<Profile>> {
+    let games = games.lock().unwrap();
+    Ok(Json(games.profile(player)?.clone()))
+}
+
+#[get("/leaderboard?<query..>")]
+fn leaderboard(query: LeaderboardQuery, games: &State<Arc<Mutex<Games>>>) -> Json<Leaderboard> {
+    let games = games.lock().unwrap();
+    Json(games.leaderboard(&query))
+}
+
+#[get("/game/<game_id>/tiles")]
+fn get_tiles(
     game_id: &str,
     games: &State<Arc<Mutex<Games>>>,
-    dictionary: &State<Arc<Dictionary>>,
-) -> Result<Json<HashMap<Player, u32>>> {
+    languages: &State<Arc<Languages>>,
+) -> Result<Json<TileSet>> {
     let mut games = games.lock().unwrap();
-    let game = games.get(game_id)?.clone();
-    Ok(Json(
-        game.get_score(dictionary, &game.settings.scoring_method),
-    ))
+    let game = games.get(game_id)?;
+    let dictionary = languages.get(&game.settings.language)?;
+    Ok(Json(dictionary.tile_set(&game.settings).clone()))
+}
+
+/// Record an answer and start a new round once everyone has answered
+fn submit_answer(
+    game_id: &str,
+    games: &mut Games,
+    answer: Answer,
+    languages: &Arc<Languages>,
+    games_state: &Arc<Mutex<Games>>,
+) -> Result<()> {
+    let game = games.get_playable(game_id)?;
+    let dictionary = languages.get(&game.settings.language)?;
+    let clock_started = game.clock_started();
+    let result = game.answer(answer, dictionary);
+    // Even a wrong first guess starts the clock
+    if !clock_started && game.clock_started() {
+        play_round(game_id, game, languages, games_state);
+    }
+    result?;
+    add_round_if_complete(game_id, games, languages, games_state)
+}
+
+fn add_round_if_complete(
+    game_id: &str,
+    games: &mut Games,
+    languages: &Arc<Languages>,
+    games_state: &Arc<Mutex<Games>>,
+) -> Result<()> {
+    let game = games.get(game_id)?;
+    if game.current_round_state() != RoundState::Complete {
+        return Ok(());
+    }
+    let languages_clone = languages.clone();
+    let games_clone = games_state.clone();
+    let game_id_clone = game_id.to_string();
+    let round_id = game.current_round().id;
+    tokio::spawn(async move {
+        finish_round(languages_clone, games_clone, game_id_clone, round_id).await
+    });
+    Ok(())
+}
+
+/// Score a completed round and start the next one once its letters are drawn
+async fn finish_round(
+    languages: Arc<Languages>,
+    games_state: Arc<Mutex<Games>>,
+    game_id: String,
+    round_id: u64,
+) -> Option<()> {
+    let (settings, round_rack, needs_best_answers) = {
+        let mut games = games_state.lock().unwrap();
+        let game = games.get(&game_id).ok()?;
+        let round = game.current_round();
+        let needs_best_answers = game.settings.percent_of_best && round.best_answers.is_empty();
+        (game.settings.clone(), round.rack(), needs_best_answers)
+    };
+    // Scoring by percent of best needs the best answers, so find them now if the background
+    // search has not yet, rather than while holding the lock
+    let best_answers = if needs_best_answers {
+        let languages = languages.clone();
+        let settings = settings.clone();
+        tokio::task::spawn_blocking(move || {
+            let dictionary = languages.get(&settings.language).ok()?;
+            let count = settings.mode.best_answer_count();
+            Some(dictionary.get_best_words_now(&round_rack, count, &settings))
+        })
+        .await
+        .expect("finding best words not to panic")
+    } else {
+        None
+    };
+    // Draw the next letters first so that a failure leaves the round to be finished later
+    let rack = draw_letters(languages.clone(), settings, Dictionary::get_next_letters)
+        .await
+        .ok()?;
+    let mut games = games_state.lock().unwrap();
+    let game = games.get(&game_id).ok()?;
+    // Another answer or the deadline may have finished the round while the letters were drawn
+    if game.current_round().id != round_id
+        || game.is_over()
+        || game.current_round_state() != RoundState::Complete
+    {
+        return None;
+    }
+    if let Some(best_answers) = best_answers {
+        if game.current_round().best_answers.is_empty() {
+            game.set_best_answers(game.rounds.len() - 1, best_answers);
+        }
+    }
+    let dictionary = languages.get(&game.settings.language).ok()?;
+    game.score_round(dictionary);
+    games.record_round(&game_id, dictionary).ok()?;
+    let game = games.get(&game_id).ok()?;
+    if game.is_over() {
+        return games.record_game(&game_id).ok();
+    }
+    game.add_round(rack);
+    start_round(&game_id, game, &languages, &games_state);
+    Some(())
+}
+
+/// Draw letters on a blocking thread, since searching the dictionary would hold up other requests
+async fn draw_letters(
+    languages: Arc<Languages>,
+    settings: GameSettings,
+    draw: fn(&Dictionary, &GameSettings) -> Result<Rack>,
+) -> Result<Rack> {
+    tokio::task::spawn_blocking(move || draw(languages.get(&settings.language)?, &settings))
+        .await
+        .expect("drawing letters not to panic")
+}
+
+/// Find the best words for the newest round and play it if its clock is running
+fn start_round(game_id: &str, game: &Game, languages: &Arc<Languages>, games: &Arc<Mutex<Games>>) {
+    let languages_clone = languages.clone();
+    let rack = game.current_round().rack();
+    let settings = game.settings.clone();
+    let games_clone = games.clone();
+    let game_id_clone = game_id.to_string();
+    let i = game.rounds.len() - 1;
+    tokio::spawn(async move {
+        get_best_words_for_round(
+            languages_clone,
+            rack,
+            settings,
+            games_clone,
+            game_id_clone,
+            i,
+        )
+        .await
+    });
+    if game.clock_started() {
+        play_round(game_id, game, languages, games);
+    }
+}
+
+/// Have the bots answer the current round and end it at its deadline, once its clock starts
+fn play_round(game_id: &str, game: &Game, languages: &Arc<Languages>, games: &Arc<Mutex<Games>>) {
+    for bot in game
+        .bots
+        .keys()
+        .filter(|bot| !game.eliminated.contains(bot))
+    {
+        spawn_bot_answer(game_id, game, bot.clone(), languages, games);
+    }
+    if let Some(deadline_ms) = game.current_round().deadline_ms {
+        let languages_clone = languages.clone();
+        let games_clone = games.clone();
+        let game_id_clone = game_id.to_string();
+        let round_id = game.current_round().id;
+        tokio::spawn(async move {
+            end_round_at_deadline(
+                languages_clone,
+                games_clone,
+                game_id_clone,
+                round_id,
+                deadline_ms,
+            )
+            .await
+        });
+    }
+}
+
+/// Move a timed round on once its deadline passes
+async fn end_round_at_deadline(
+    languages: Arc<Languages>,
+    games_state: Arc<Mutex<Games>>,
+    game_id: String,
+    round_id: u64,
+    deadline_ms: u64,
+) -> Option<()> {
+    let wait_ms = deadline_ms.saturating_sub(types::now_ms());
+    tokio::time::sleep(Duration::from_millis(wait_ms)).await;
+    let mut games = games_state.lock().unwrap();
+    let game = games.get(&game_id).ok()?;
+    // The letters may have been rerolled, or the clock stopped and started again, since this
+    // deadline was set
+    if game.current_round().id != round_id || game.current_round().deadline_ms != Some(deadline_ms)
+    {
+        return None;
+    }
+    // Rather than moving on to round after round that no one plays, wait for the players
+    if game.current_round().all_answers().next().is_none() {
+        game.stop_clock();
+        return Some(());
+    }
+    add_round_if_complete(&game_id, &mut games, &languages, &games_state).ok()
+}
+
+fn spawn_bot_answer(
+    game_id: &str,
+    game: &Game,
+    bot: Player,
+    languages: &Arc<Languages>,
+    games: &Arc<Mutex<Games>>,
+) {
+    let languages_clone = languages.clone();
+    let games_clone = games.clone();
+    let game_id_clone = game_id.to_string();
+    let round_id = game.current_round().id;
+    tokio::spawn(async move {
+        answer_for_bot(languages_clone, games_clone, game_id_clone, bot, round_id).await
+    });
+}
+
+async fn answer_for_bot(
+    languages: Arc<Languages>,
+    games_state: Arc<Mutex<Games>>,
+    game_id: String,
+    bot: Player,
+    round_id: u64,
+) -> Option<()> {
+    let (bot_settings, settings, rack) = {
+        let mut games = games_state.lock().unwrap();
+        let game = games.get(&game_id).ok()?;
+        let round = game.current_round();
+        if round.id != round_id {
+            return None;
+        }
+        (
+            game.bots.get(&bot)?.clone(),
+            game.settings.clone(),
+            round.rack(),
+        )
+    };
+    tokio::time::sleep(Duration::from_secs(bot_settings.answer_delay_seconds)).await;
+    let dictionary = languages.get(&settings.language).ok()?;
+    let words = dictionary
+        .get_best_words(&rack, usize::MAX, &settings)
+        .await;
+    let word = bot_settings
+        .choose_word(&words)
+        .map(|word_info| word_info.word.clone());
+    let mut games = games_state.lock().unwrap();
+    let game = games.get(&game_id).ok()?;
+    // The bot may have been removed, or the round finished or rerolled, while it was thinking
+    if !game.bots.contains_key(&bot) || game.current_round().id != round_id {
+        return None;
+    }
+    if let Some(word) = word {
+        let answer = Answer {
+            player: bot.clone(),
+            answer: word,
+        };
+        if submit_answer(&game_id, &mut games, answer, &languages, &games_state).is_ok() {
+            return Some(());
+        }
+    }
+    // A bot that cannot play a word gives up so the round can still finish
+    games.get(&game_id).ok()?.forfeit(bot).ok()?;
+    add_round_if_complete(&game_id, &mut games, &languages, &games_state).ok()
 }
 
 async fn get_best_words_for_round(
-    dictionary: Arc<Dictionary>,
-    tiles: Vec<char>,
-    scoring_method: ScoringMethod,
+    languages: Arc<Languages>,
+    rack: Rack,
+    settings: GameSettings,
     games: Arc<Mutex<Games>>,
     game_id: String,
     round_number: usize,
 ) -> Option<()> {
-    let best_answers = dictionary.get_best_words(&tiles, 5, &scoring_method).await;
+    let dictionary = languages.get(&settings.language).ok()?;
+    let best_answers = dictionary
+        .get_best_words(&rack, settings.mode.best_answer_count(), &settings)
+        .await;
     let mut games = games.lock().unwrap();
     let game = games.get(&game_id).ok()?;
-    let round = game.rounds.get_mut(round_number)?;
-    round.best_answers = best_answers;
-    Some(())
+    let round = game.rounds.get(round_number)?;
+    // The letters may have been rerolled while the words were being found
+    if round.letters != rack.letters {
+        return None;
+    }
+    games
+        .set_best_answers(&game_id, round_number, best_answers)
+        .ok()
+}
+
+/// How often changed statistics are written to their file
+const STATS_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Write the statistics to their file if they have changed, without holding the lock
+async fn save_stats(games_state: Arc<Mutex<Games>>) {
+    let stats = games_state.lock().unwrap().take_stats_to_save();
+    if let Some((path, json)) = stats {
+        tokio::task::spawn_blocking(move || write_stats(&path, &json))
+            .await
+            .expect("saving stats not to panic");
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -175,6 +632,18 @@ struct Opt {
         possible_values = &["off", "debug", "normal", "critical"]
     )]
     log_level: LogLevel,
+    /// A JSON file with the tiles to use for English when no language packs are given.
+    #[structopt(long = "tiles")]
+    tiles: Option<String>,
+    /// A JSON file listing the language packs games can be played in.
+    #[structopt(long = "languages")]
+    languages: Option<String>,
+    /// A list of words to flag as offensive for English when no language packs are given.
+    #[structopt(long = "offensive-words")]
+    offensive_words: Option<String>,
+    /// A JSON file to keep player profiles, ratings and leaderboards in across restarts.
+    #[structopt(long = "stats")]
+    stats: Option<String>,
 }
 
 #[launch]
@@ -186,6 +655,19 @@ fn rocket() -> _ {
         log_level: opt.log_level,
         ..Config::default()
     };
+    let language_packs = match opt.languages {
+        Some(path) => LanguagePack::from_file(&path),
+        None => {
+            let tile_set = opt
+                .tiles
+                .as_deref()
+                .map_or_else(TileSet::default, TileSet::from_file);
+            vec![LanguagePack {
+                offensive_words: opt.offensive_words,
+                ..LanguagePack::english("word-list.txt", tile_set)
+            }]
+        }
+    };
 
     let cors = CorsOptions::default()
         .allowed_origins(AllowedOrigins::all())
@@ -209,14 +691,47 @@ fn rocket() -> _ {
             "/",
             routes![
                 create_game,
+                list_games,
+                quick_match,
                 join_game,
                 game,
                 answer,
+                add_bot,
+                start,
+                vote_reroll,
+                hint,
+                challenge,
+                vote_on_challenge,
+                create_team,
+                join_team,
                 exit_game,
                 delete_game,
-                get_score
+                get_score,
+                profile,
+                leaderboard,
+                get_tiles,
+                export_game,
+                import_game
             ],
         )
-        .manage(Arc::new(Mutex::new(Games::default())))
-        .manage(Arc::new(Dictionary::new("word-list.txt")))
+        .manage(Arc::new(Mutex::new(
+            opt.stats
+                .map_or_else(Games::default, Games::with_stats_file),
+        )))
+        .manage(Arc::new(Languages::new(language_packs)))
+        .attach(AdHoc::on_liftoff("Save stats", |rocket| {
+            let games_state = rocket.state::<Arc<Mutex<Games>>>().unwrap().clone();
+            Box::pin(async move {
+                tokio::spawn(async move {
+                    loop {
+                        tokio::time::sleep(STATS_SAVE_INTERVAL).await;
+                        save_stats(games_state.clone()).await;
+                    }
+                });
+            })
+        }))
+        .attach(AdHoc::on_shutdown("Save stats", |rocket| {
+            let games_state = rocket.state::<Arc<Mutex<Games>>>().unwrap().clone();
+            Box::pin(save_stats(games_state))
+        }))
 }
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dictionary::Dictionary,
+    ratings::{update_ratings, INITIAL_RATING},
+    types::{Error, Game, Player, Result},
+};
+
+/// A player's statistics across every game they have played
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub(crate) struct Profile {
+    /// The number of games the player has answered in
+    pub(crate) games_played: u32,
+    /// The number of completed rounds the player took part in
+    pub(crate) rounds_played: u32,
+    /// The number of rounds where no one scored more than the player
+    pub(crate) rounds_won: u32,
+    /// The player's score across every round, after hint penalties
+    pub(crate) total_score: u32,
+    pub(crate) average_score: f64,
+    /// The number of rounds where the player's best word was compared to the round's best word
+    pub(crate) rounds_with_best: u32,
+    /// The sum of the percent of the best word's score reached in those rounds
+    pub(crate) total_percent_of_best: u32,
+    pub(crate) average_percent_of_best: f64,
+    pub(crate) longest_word: Option<String>,
+    pub(crate) highest_scoring_word: Option<String>,
+    /// The score of the highest scoring word before any adjustments
+    pub(crate) highest_word_score: u32,
+    /// The number of words that used every tile
+    pub(crate) bingos: u32,
+    /// The player's Elo rating, which is unset until their first rated game
+    pub(crate) rating: Option<f64>,
+    /// The number of finished games with other players that changed the player's rating
+    pub(crate) rated_games: u32,
+}
+
+/// The profiles of every player who has finished a round, keyed by name
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct Profiles(HashMap<Player, Profile>);
+
+impl Profiles {
+    pub(crate) fn get(&self, player: &str) -> Result<&Profile> {
+        self.0.get(player).ok_or(Error::PlayerNotFound)
+    }
+
+    /// Add the results of the game's current round, which should have just been scored
+    pub(crate) fn record_round(&mut self, game: &Game, dictionary: &Dictionary) {
+        let round_number = game.rounds.len() - 1;
+        let round = game.current_round();
+        let round_scores = game.round_scores(round);
+        let winning_score = round_scores.values().copied().max().unwrap_or(0);
+        for (player, score) in &round_scores {
+            // Bots share names between games, so their results would mix
+            if game.bots.contains_key(*player) {
+                continue;
+            }
+            let played_before = game.rounds[..round_number]
+                .iter()
+                .any(|round| game.round_scores(round).contains_key(player));
+            let profile = self.0.entry((*player).clone()).or_default();
+            if !played_before {
+                profile.games_played += 1;
+            }
+            profile.rounds_played += 1;
+            if *score == winning_score && *score > 0 {
+                profile.rounds_won += 1;
+            }
+            profile.total_score += score;
+            profile.average_score = profile.total_score as f64 / profile.rounds_played as f64;
+
+            // Forfeited answers have no word score and say nothing about the player's words
+            let words = round
+                .all_answers()
+                .filter(|answer| answer.player == **player && answer.word_score > 0);
+            for answer in words {
+                let length = answer.answer.chars().count();
+                if profile
+                    .longest_word
+                    .as_ref()
+                    .is_none_or(|word| length > word.chars().count())
+                {
+                    profile.longest_word = Some(answer.answer.clone());
+                }
+                if answer.word_score > profile.highest_word_score {
+                    profile.highest_word_score = answer.word_score;
+                    profile.highest_scoring_word = Some(answer.answer.clone());
+                }
+                // A word with overlapping tiles is a bingo if any way of spelling it uses every tile
+                let bingo = dictionary
+                    .tile_set(&game.settings)
+                    .tokenizations(&dictionary.normalize(&answer.answer))
+                    .iter()
+                    .any(|tiles| {
+                        tiles.len() == round.letters.len()
+                            && Dictionary::check_word_uses_letters(&round.letters, tiles).is_some()
+                    });
+                if bingo {
+                    profile.bingos += 1;
+                }
+            }
+        }
+        self.record_percent_of_best(game, round_number);
+    }
+
+    /// Add how close each player came to a recorded round's best word, once it is known
+    pub(crate) fn record_percent_of_best(&mut self, game: &Game, round_number: usize) {
+        let Some(round) = game.rounds.get(round_number) else {
+            return;
+        };
+        for player in game.round_scores(round).keys() {
+            if game.bots.contains_key(*player) {
+                continue;
+            }
+            let (Some(percent), Some(profile)) =
+                (round.percent_of_best(player), self.0.get_mut(*player))
+            else {
+                continue;
+            };
+            profile.rounds_with_best += 1;
+            profile.total_percent_of_best += percent;
+            profile.average_percent_of_best =
+                profile.total_percent_of_best as f64 / profile.rounds_with_best as f64;
+        }
+    }
+
+    /// Update the ratings of a finished game's players from the standings of its scored rounds
+    pub(crate) fn record_game(&mut self, game: &Game) {
+        let scores = game.get_final_score();
+        let players: Vec<&Player> = scores
+            .players
+            .keys()
+            .filter(|player| !game.bots.contains_key(*player))
+            .collect();
+        if players.len() < 2 || !game.rounds.iter().any(|round| round.scored) {
+            return;
+        }
+        // Elimination games are ranked by when players were knocked out, others by score
+        let places: Vec<usize> = players
+            .iter()
+            .map(
+                |player| match scores.ranking.iter().position(|p| p == *player) {
+                    Some(place) => place,
+                    None => {
+                        let score = scores.players[*player];
+                        players
+                            .iter()
+                            .filter(|other| scores.players[**other] > score)
+                            .count()
+                    }
+                },
+            )
+            .collect();
+        let ratings: Vec<f64> = players
+            .iter()
+            .map(|player| {
+                self.0
+                    .get(*player)
+                    .and_then(|profile| profile.rating)
+                    .unwrap_or(INITIAL_RATING)
+            })
+            .collect();
+        let new_ratings = update_ratings(&ratings, &places);
+        for (player, rating) in players.into_iter().zip(new_ratings) {
+            let profile = self.0.entry(player.clone()).or_default();
+            profile.rating = Some(rating);
+            profile.rated_games += 1;
+        }
+    }
+}
+
+#[test]
+fn test_record_round() {
+    use crate::{
+        dictionary::{remorse_rack, test_dictionary},
+        types::{Answer, GameSettings},
+    };
+
+    let dictionary = test_dictionary();
+    let mut profiles = Profiles::default();
+    let mut game = Game {
+        settings: GameSettings {
+            max_rounds: Some(2),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let answer = |player: &str, word: &str| Answer {
+        player: String::from(player),
+        answer: String::from(word),
+    };
+    game.add_round(remorse_rack());
+    game.add_player(String::from("a")).unwrap();
+    game.add_player(String::from("b")).unwrap();
+    game.answer(answer("a", "remorse"), dictionary).unwrap();
+    game.answer(answer("b", "more"), dictionary).unwrap();
+    game.score_round(dictionary);
+    game.set_best_answers(
+        0,
+        dictionary.get_best_words_now(&remorse_rack(), 1, &game.settings),
+    );
+    profiles.record_round(&game, dictionary);
+    game.add_round(remorse_rack());
+    game.answer(answer("a", "ore"), dictionary).unwrap();
+    game.answer(answer("b", "remorse"), dictionary).unwrap();
+    game.score_round(dictionary);
+    profiles.record_round(&game, dictionary);
+    // The best answers can be found after the round is recorded
+    assert_eq!(profiles.get("a").unwrap().rounds_with_best, 1);
+    game.set_best_answers(
+        1,
+        dictionary.get_best_words_now(&remorse_rack(), 1, &game.settings),
+    );
+    profiles.record_percent_of_best(&game, 1);
+
+    let a = profiles.get("a").unwrap();
+    assert_eq!(a.games_played, 1);
+    assert_eq!(a.rounds_played, 2);
+    assert_eq!(a.rounds_won, 1);
+    assert_eq!(a.total_score, 12);
+    assert_eq!(a.average_score, 6.0);
+    // REMORSE is the best word, so ORE reaches a third of its score
+    assert_eq!(a.average_percent_of_best, 66.5);
+    assert_eq!(a.longest_word.as_deref(), Some("remorse"));
+    assert_eq!(a.highest_scoring_word.as_deref(), Some("remorse"));
+    assert_eq!(a.bingos, 1);
+    assert_eq!(profiles.get("b").unwrap().rounds_won, 1);
+    assert!(profiles.get("c").is_err());
+    assert_eq!(a.rating, None);
+    assert!(game.is_over());
+
+    // B finished with 15 points to A's 12, and a round that is not scored does not count
+    game.add_round(remorse_rack());
+    game.answer(answer("a", "remorse"), dictionary).unwrap();
+    profiles.record_game(&game);
+    let a = profiles.get("a").unwrap();
+    assert_eq!(a.rated_games, 1);
+    assert_eq!(a.rating, Some(1484.0));
+    assert_eq!(profiles.get("b").unwrap().rating, Some(1516.0));
+}
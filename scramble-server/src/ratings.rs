@@ -0,0 +1,53 @@
+/// The rating a player has before their first rated game
+pub(crate) const INITIAL_RATING: f64 = 1500.0;
+
+/// The most a player's rating can change in one game
+const K_FACTOR: f64 = 32.0;
+
+/// The Elo ratings of a game's players after it finishes, given their ratings and places
+///
+/// Each player is treated as having played every other player head to head, winning against
+/// those placed below them and drawing with those in the same place. Places start from zero.
+pub(crate) fn update_ratings(ratings: &[f64], places: &[usize]) -> Vec<f64> {
+    let opponents = ratings.len().saturating_sub(1).max(1) as f64;
+    ratings
+        .iter()
+        .zip(places)
+        .enumerate()
+        .map(|(i, (rating, place))| {
+            let change: f64 = ratings
+                .iter()
+                .zip(places)
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, (opponent_rating, opponent_place))| {
+                    let expected = 1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0));
+                    let actual = match place.cmp(opponent_place) {
+                        std::cmp::Ordering::Less => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.0,
+                    };
+                    actual - expected
+                })
+                .sum();
+            rating + K_FACTOR * change / opponents
+        })
+        .collect()
+}
+
+#[test]
+fn test_update_ratings() {
+    assert_eq!(
+        update_ratings(&[INITIAL_RATING, INITIAL_RATING], &[0, 1]),
+        [1516.0, 1484.0]
+    );
+    assert_eq!(
+        update_ratings(&[INITIAL_RATING, INITIAL_RATING], &[0, 0]),
+        [INITIAL_RATING, INITIAL_RATING]
+    );
+    // Beating a much stronger player gains more than beating an equal one
+    let ratings = update_ratings(&[1400.0, 1800.0, 1500.0], &[0, 1, 2]);
+    assert!(ratings[0] - 1400.0 > 16.0);
+    assert!(ratings[1] < 1800.0);
+    assert!(ratings[2] < 1500.0);
+}
@@ -0,0 +1,422 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use rand::Rng;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+use crate::dictionary::{remorse_rack, test_dictionary};
+use crate::{
+    dictionary::{Dictionary, WordInfo},
+    tiles::TileSet,
+    types::Player,
+};
+
+/// The bonus given by `NormalWithBingo` for a word that uses every tile
+const BINGO_BONUS: u32 = 50;
+
+/// The speed bonus for a correct answer given the moment the round starts
+const MAX_SPEED_BONUS: u32 = 10;
+
+/// How long after the round starts a correct answer still earns a speed bonus
+const SPEED_BONUS_WINDOW_MS: u64 = 30_000;
+
+/// The multipliers a bonus letter square can have
+const LETTER_MULTIPLIERS: [u32; 2] = [2, 3];
+
+/// The multiplier of the bonus word square
+const WORD_MULTIPLIER: u32 = 2;
+
+/// Everything a scorer can look at besides the word itself
+pub(crate) struct ScoringContext<'a> {
+    /// The letters that were available in the round
+    pub(crate) letters: &'a [String],
+    /// The letters and point values the game is played with
+    pub(crate) tile_set: &'a TileSet,
+    /// The bonus squares on the round's letters
+    pub(crate) bonuses: &'a Bonuses,
+}
+
+/// Scrabble-style bonus squares on a round's letters, keyed by index in the letters
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct Bonuses {
+    /// How many times over each bonus letter's points count
+    pub(crate) letter_multipliers: HashMap<usize, u32>,
+    /// The letter that multiplies the whole word's score when it is used
+    pub(crate) word_multiplier: Option<WordMultiplier>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) struct WordMultiplier {
+    /// The index of the letter in the round's letters
+    pub(crate) index: usize,
+    /// How many times over the word's score counts
+    pub(crate) multiplier: u32,
+}
+
+impl Bonuses {
+    /// One bonus letter square and one bonus word square on different letters
+    pub(crate) fn random(number_of_tiles: usize, rng: &mut impl Rng) -> Self {
+        let letter_index = rng.gen_range(0..number_of_tiles);
+        let word_index = (letter_index + rng.gen_range(1..number_of_tiles)) % number_of_tiles;
+        Self {
+            letter_multipliers: HashMap::from([(
+                letter_index,
+                LETTER_MULTIPLIERS[rng.gen_range(0..LETTER_MULTIPLIERS.len())],
+            )]),
+            word_multiplier: Some(WordMultiplier {
+                index: word_index,
+                multiplier: WORD_MULTIPLIER,
+            }),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.letter_multipliers.is_empty() && self.word_multiplier.is_none()
+    }
+
+    /// How much a letter's square is worth, with the word square worth the most
+    fn value(&self, index: usize) -> (bool, u32) {
+        (
+            self.word_multiplier.is_some_and(|w| w.index == index),
+            self.letter_multipliers.get(&index).copied().unwrap_or(1),
+        )
+    }
+
+    /// Move the letters a word uses onto the most valuable squares holding the same letter
+    fn best_positions(&self, letters: &[String], used: &[usize]) -> Vec<usize> {
+        let mut uses: HashMap<&str, usize> = HashMap::new();
+        for &index in used {
+            *uses.entry(&letters[index]).or_default() += 1;
+        }
+        let mut positions = Vec::new();
+        for (letter, count) in uses {
+            let mut squares: Vec<usize> = (0..letters.len())
+                .filter(|&index| letters[index] == letter)
+                .collect();
+            squares.sort_by_key(|&index| Reverse(self.value(index)));
+            positions.extend(squares.into_iter().take(count));
+        }
+        positions
+    }
+
+    /// Add the extra points of the bonus letters used, if the score is made of the letters'
+    /// points, then apply the word multiplier
+    fn apply(
+        &self,
+        score: u32,
+        tiles: &[String],
+        letter_points: bool,
+        context: &ScoringContext,
+    ) -> u32 {
+        if self.is_empty() {
+            return score;
+        }
+        let Some(used) = Dictionary::check_word_uses_letters(context.letters, tiles) else {
+            return score;
+        };
+        let positions = self.best_positions(context.letters, &used);
+        let mut score = score;
+        if letter_points {
+            for &index in &positions {
+                if let Some(multiplier) = self.letter_multipliers.get(&index) {
+                    score += context.tile_set.points(&context.letters[index]) * (multiplier - 1);
+                }
+            }
+        }
+        match self.word_multiplier {
+            Some(word) if positions.contains(&word.index) => score * word.multiplier,
+            _ => score,
+        }
+    }
+}
+
+pub(crate) trait Scorer: Sync {
+    /// The name used to select this scorer in the game settings
+    fn name(&self) -> &'static str;
+    /// The score of a playable word spelled with the round's letters
+    fn score(&self, word_info: &WordInfo, context: &ScoringContext) -> u32;
+    /// Whether the score adds up the letters' points, which bonus letter squares multiply
+    fn uses_letter_points(&self) -> bool {
+        false
+    }
+}
+
+/// Every scorer that can be selected in the game settings
+static SCORERS: &[&dyn Scorer] = &[
+    &Normal,
+    &Length,
+    &SquaredLength,
+    &LengthTimesDistinct,
+    &RarityWeighted,
+    &NormalWithBingo,
+];
+
+/// The sum of the letter scores
+struct Normal;
+
+impl Scorer for Normal {
+    fn name(&self) -> &'static str {
+        "Normal"
+    }
+
+    fn score(&self, word_info: &WordInfo, context: &ScoringContext) -> u32 {
+        Dictionary::calculate_score(&word_info.tiles, context.tile_set)
+    }
+
+    fn uses_letter_points(&self) -> bool {
+        true
+    }
+}
+
+/// One point per letter
+struct Length;
+
+impl Scorer for Length {
+    fn name(&self) -> &'static str {
+        "Length"
+    }
+
+    fn score(&self, word_info: &WordInfo, _: &ScoringContext) -> u32 {
+        word_info.word.chars().count() as u32
+    }
+}
+
+/// The number of letters squared, so long words are worth much more
+struct SquaredLength;
+
+impl Scorer for SquaredLength {
+    fn name(&self) -> &'static str {
+        "SquaredLength"
+    }
+
+    fn score(&self, word_info: &WordInfo, _: &ScoringContext) -> u32 {
+        let length = word_info.word.chars().count() as u32;
+        length * length
+    }
+}
+
+/// The number of letters times the number of distinct letters
+struct LengthTimesDistinct;
+
+impl Scorer for LengthTimesDistinct {
+    fn name(&self) -> &'static str {
+        "LengthTimesDistinct"
+    }
+
+    fn score(&self, word_info: &WordInfo, _: &ScoringContext) -> u32 {
+        let length = word_info.word.chars().count() as u32;
+        let distinct = word_info.word.chars().collect::<HashSet<_>>().len() as u32;
+        length * distinct
+    }
+}
+
+/// The sum of how rare each letter is among all the tiles
+struct RarityWeighted;
+
+impl Scorer for RarityWeighted {
+    fn name(&self) -> &'static str {
+        "RarityWeighted"
+    }
+
+    fn score(&self, word_info: &WordInfo, context: &ScoringContext) -> u32 {
+        word_info
+            .tiles
+            .iter()
+            .map(|tile| context.tile_set.letter_rarity(tile))
+            .sum()
+    }
+}
+
+/// The sum of the letter scores plus a bonus for using every tile
+struct NormalWithBingo;
+
+impl Scorer for NormalWithBingo {
+    fn name(&self) -> &'static str {
+        "NormalWithBingo"
+    }
+
+    fn score(&self, word_info: &WordInfo, context: &ScoringContext) -> u32 {
+        let score = Normal.score(word_info, context);
+        if word_info.tiles.len() == context.letters.len() {
+            score + BINGO_BONUS
+        } else {
+            score
+        }
+    }
+
+    fn uses_letter_points(&self) -> bool {
+        true
+    }
+}
+
+/// What happens to a word that more than one player found in the same round
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) enum DuplicateAnswers {
+    /// Every player keeps the full score
+    #[default]
+    Keep,
+    /// The word scores nothing for anyone
+    Cancel,
+    /// The score is split evenly between the players
+    Split,
+}
+
+/// A change made to an answer's score once the round was complete
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub(crate) enum ScoreAdjustment {
+    /// The word was cancelled because these players also found it
+    Cancelled { shared_with: Vec<Player> },
+    /// The score was split with these players who also found the word
+    Split { shared_with: Vec<Player> },
+    /// The score was turned into a percentage of the round's best score
+    PercentOfBest { best_score: u32 },
+    /// Points were added for answering quickly
+    SpeedBonus { bonus: u32 },
+    /// The players voted that the word should not have been accepted
+    RejectedByChallenge,
+    /// The players voted that the word should have been accepted
+    AcceptedByChallenge,
+}
+
+/// The bonus for a correct answer, shrinking to nothing over the bonus window
+pub(crate) fn speed_bonus(answer_time_ms: u64) -> u32 {
+    let remaining_ms = SPEED_BONUS_WINDOW_MS.saturating_sub(answer_time_ms);
+    (MAX_SPEED_BONUS as u64 * remaining_ms / SPEED_BONUS_WINDOW_MS) as u32
+}
+
+/// The scorer selected for a game, serialized as the scorer's name
+#[derive(Clone, Copy)]
+pub(crate) struct ScoringMethod(&'static dyn Scorer);
+
+impl ScoringMethod {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        SCORERS
+            .iter()
+            .find(|scorer| scorer.name() == name)
+            .map(|scorer| ScoringMethod(*scorer))
+    }
+
+    pub(crate) fn score(&self, word_info: &WordInfo, context: &ScoringContext) -> u32 {
+        let score = self.0.score(word_info, context);
+        let letter_points = self.0.uses_letter_points();
+        context
+            .bonuses
+            .apply(score, &word_info.tiles, letter_points, context)
+    }
+}
+
+impl Default for ScoringMethod {
+    fn default() -> Self {
+        ScoringMethod(&Normal)
+    }
+}
+
+impl PartialEq for ScoringMethod {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name() == other.0.name()
+    }
+}
+
+impl fmt::Debug for ScoringMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.name())
+    }
+}
+
+impl Serialize for ScoringMethod {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ScoringMethod {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        ScoringMethod::from_name(&name)
+            .ok_or_else(|| de::Error::custom(format!("unknown scoring method {name}")))
+    }
+}
+
+#[test]
+fn test_scorers() {
+    let dictionary = test_dictionary();
+    let letters = remorse_rack().letters;
+    let tile_set = TileSet::default();
+    let context = ScoringContext {
+        letters: &letters,
+        tile_set: &tile_set,
+        bonuses: &Bonuses::default(),
+    };
+    let remorse = dictionary.get_word_info_if_playable("remorse").unwrap();
+    let score = |name: &str| {
+        ScoringMethod::from_name(name)
+            .unwrap()
+            .score(remorse, &context)
+    };
+    assert_eq!(score("Normal"), 9);
+    assert_eq!(score("Length"), 7);
+    assert_eq!(score("SquaredLength"), 49);
+    assert_eq!(score("LengthTimesDistinct"), 35);
+    assert_eq!(score("RarityWeighted"), 16);
+    assert_eq!(score("NormalWithBingo"), 59);
+    assert!(ScoringMethod::from_name("NotAScorer").is_none());
+}
+
+#[test]
+fn test_speed_bonus() {
+    assert_eq!(speed_bonus(0), 10);
+    assert_eq!(speed_bonus(15_000), 5);
+    assert_eq!(speed_bonus(29_999), 0);
+    assert_eq!(speed_bonus(60_000), 0);
+}
+
+#[test]
+fn test_bonuses() {
+    let dictionary = test_dictionary();
+    let letters = remorse_rack().letters;
+    let tile_set = TileSet::default();
+    let more = dictionary.get_word_info_if_playable("more").unwrap();
+    let score = |bonuses: &Bonuses| {
+        let context = ScoringContext {
+            letters: &letters,
+            tile_set: &tile_set,
+            bonuses,
+        };
+        ScoringMethod::default().score(more, &context)
+    };
+    let mut bonuses = Bonuses::default();
+    assert_eq!(score(&bonuses), 6);
+    // A triple letter on the M adds its 3 points twice more
+    bonuses.letter_multipliers.insert(2, 3);
+    assert_eq!(score(&bonuses), 12);
+    // The word uses one of the two Rs, which is placed on the word square
+    bonuses.word_multiplier = Some(WordMultiplier {
+        index: 4,
+        multiplier: 2,
+    });
+    assert_eq!(score(&bonuses), 24);
+    // Letters the word does not use give no bonus
+    bonuses.letter_multipliers = HashMap::from([(5, 3)]);
+    bonuses.word_multiplier = None;
+    assert_eq!(score(&bonuses), 6);
+    // Scores that are not made of letter points only get the word multiplier
+    let bonuses = Bonuses {
+        letter_multipliers: HashMap::from([(2, 3)]),
+        word_multiplier: Some(WordMultiplier {
+            index: 4,
+            multiplier: 2,
+        }),
+    };
+    let context = ScoringContext {
+        letters: &letters,
+        tile_set: &tile_set,
+        bonuses: &bonuses,
+    };
+    let length = ScoringMethod::from_name("Length").unwrap();
+    assert_eq!(length.score(more, &context), 8);
+}
@@ -0,0 +1,201 @@
+use std::{borrow::Cow, fs::File, io::BufReader};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct Tile {
+    /// The letter on the tile, which may be more than one character such as CH
+    pub(crate) letter: Cow<'static, str>,
+    /// The number of points the letter is worth
+    pub(crate) points: u32,
+    /// The number of copies of the tile in the bag
+    pub(crate) count: u32,
+}
+
+macro_rules! new_tile {
+    ($letter:expr, $points:expr, $count:expr) => {
+        Tile {
+            letter: Cow::Borrowed($letter),
+            points: $points,
+            count: $count,
+        }
+    };
+}
+
+const TILES: [Tile; 26] = [
+    // new_tile!("?", 0, 2),
+    new_tile!("E", 1, 12),
+    new_tile!("A", 1, 9),
+    new_tile!("I", 1, 9),
+    new_tile!("O", 1, 8),
+    new_tile!("N", 1, 6),
+    new_tile!("R", 1, 6),
+    new_tile!("T", 1, 6),
+    new_tile!("L", 1, 4),
+    new_tile!("S", 1, 4),
+    new_tile!("U", 1, 4),
+    new_tile!("D", 2, 4),
+    new_tile!("G", 2, 3),
+    new_tile!("B", 3, 2),
+    new_tile!("C", 3, 2),
+    new_tile!("M", 3, 2),
+    new_tile!("P", 3, 2),
+    new_tile!("F", 4, 2),
+    new_tile!("H", 4, 2),
+    new_tile!("V", 4, 2),
+    new_tile!("W", 4, 2),
+    new_tile!("Y", 4, 2),
+    new_tile!("K", 5, 1),
+    new_tile!("J", 8, 1),
+    new_tile!("X", 8, 1),
+    new_tile!("Q", 10, 1),
+    new_tile!("Z", 10, 1),
+];
+
+/// The letters, point values and counts of the tiles in the bag
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub(crate) struct TileSet(Vec<Tile>);
+
+impl Default for TileSet {
+    fn default() -> Self {
+        Self(TILES.to_vec())
+    }
+}
+
+impl TileSet {
+    /// Read a tile set from a JSON list of tiles
+    pub(crate) fn from_file(path: &str) -> Self {
+        let file = File::open(path).unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+
+    pub(crate) fn is_valid(&self, number_of_tiles: u32) -> bool {
+        let letters: Vec<&str> = self.0.iter().map(|t| t.letter.as_ref()).collect();
+        let total_count: u32 = self.0.iter().map(|t| t.count).sum();
+        letters
+            .iter()
+            .enumerate()
+            .all(|(i, letter)| !letter.is_empty() && !letters[..i].contains(letter))
+            && total_count >= number_of_tiles
+    }
+
+    /// Apply a language's normalization to the letter on every tile
+    pub(crate) fn normalized(&self, normalize: impl Fn(&str) -> String) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|t| Tile {
+                    letter: Cow::Owned(normalize(&t.letter)),
+                    ..t.clone()
+                })
+                .collect(),
+        )
+    }
+
+    /// Every tile in the bag, with each letter repeated by its count
+    pub(crate) fn all_tiles(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .flat_map(|tile| vec![tile.letter.to_string(); tile.count as usize])
+            .collect()
+    }
+
+    pub(crate) fn points(&self, letter: &str) -> u32 {
+        self.0
+            .iter()
+            .find(|t| t.letter == letter)
+            .map_or(0, |t| t.points)
+    }
+
+    /// How many times rarer a letter is than the most common letter
+    pub(crate) fn letter_rarity(&self, letter: &str) -> u32 {
+        let max_count = self.0.iter().map(|t| t.count).max().unwrap_or(0);
+        match self.0.iter().find(|t| t.letter == letter) {
+            Some(tile) if tile.count > 0 => max_count / tile.count,
+            _ => 0,
+        }
+    }
+
+    /// Split a normalized word into the tiles needed to spell it, preferring
+    /// the longest tile at each position, or `None` if some part has no tile
+    pub(crate) fn tokenize(&self, word: &str) -> Option<Vec<String>> {
+        self.tokenizations(word).into_iter().next()
+    }
+
+    /// Every way to split a normalized word into tiles, starting with the one that prefers the
+    /// longest tile at each position
+    pub(crate) fn tokenizations(&self, word: &str) -> Vec<Vec<String>> {
+        if word.is_empty() {
+            return vec![Vec::new()];
+        }
+        let mut tiles: Vec<&Tile> = self
+            .0
+            .iter()
+            .filter(|t| !t.letter.is_empty() && word.starts_with(t.letter.as_ref()))
+            .collect();
+        tiles.sort_by_key(|t| std::cmp::Reverse(t.letter.len()));
+        tiles
+            .into_iter()
+            .flat_map(|tile| {
+                self.tokenizations(&word[tile.letter.len()..])
+                    .into_iter()
+                    .map(|mut rest| {
+                        rest.insert(0, tile.letter.to_string());
+                        rest
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether some tiles have more than one letter, so a word may be spelled more than one way
+    pub(crate) fn has_multi_letter_tiles(&self) -> bool {
+        self.0.iter().any(|t| t.letter.chars().count() > 1)
+    }
+}
+
+#[test]
+fn test_tiles() {
+    let num_tiles: u32 = TILES.iter().map(|t| t.count).sum();
+    assert_eq!(num_tiles, 98);
+    let total_points: u32 = TILES.iter().map(|t| t.points * t.count).sum::<u32>();
+    assert_eq!(total_points, 187);
+}
+
+#[test]
+fn test_tile_set_from_json() {
+    let tile_set: TileSet = serde_json::from_str(
+        r#"[{"letter": "a", "points": 2, "count": 3}, {"letter": "B", "points": 5, "count": 1}]"#,
+    )
+    .unwrap();
+    let tile_set = tile_set.normalized(str::to_uppercase);
+    assert_eq!(tile_set.all_tiles(), vec!["A", "A", "A", "B"]);
+    assert_eq!(tile_set.points("A"), 2);
+    assert_eq!(tile_set.letter_rarity("B"), 3);
+    assert!(tile_set.is_valid(4));
+    assert!(!tile_set.is_valid(5));
+}
+
+#[test]
+fn test_tokenize_digraphs() {
+    let tile_set: TileSet = serde_json::from_str(
+        r#"[
+            {"letter": "C", "points": 3, "count": 4},
+            {"letter": "CH", "points": 5, "count": 1},
+            {"letter": "H", "points": 4, "count": 2},
+            {"letter": "O", "points": 1, "count": 9},
+            {"letter": "Ñ", "points": 8, "count": 1}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        tile_set.tokenize("COCHO").unwrap(),
+        vec!["C", "O", "CH", "O"]
+    );
+    assert_eq!(tile_set.tokenize("ÑOC").unwrap(), vec!["Ñ", "O", "C"]);
+    assert!(tile_set.tokenize("CAO").is_none());
+    assert_eq!(
+        tile_set.tokenizations("CHO"),
+        vec![vec!["CH", "O"], vec!["C", "H", "O"]]
+    );
+}
@@ -1,16 +1,35 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use rocket::{
     http::{ContentType, Status},
     response::{self, Responder},
     Request, Response,
 };
 use serde::{Deserialize, Serialize};
-use std::io::Cursor;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{
     collections::{HashMap, HashSet},
     fmt,
 };
 
-use crate::dictionary::{Dictionary, WordInfo};
+#[cfg(test)]
+use crate::dictionary::{remorse_rack, test_dictionary, test_rack};
+use crate::{
+    bots::BotSettings,
+    challenges::{Challenge, ChallengeKind, ChallengeOutcome},
+    dictionary::{Dictionary, OffensiveWordFilter, Rack, WordInfo},
+    events::GameEvent,
+    language::DEFAULT_LANGUAGE,
+    leaderboards::{Leaderboard, LeaderboardQuery, Leaderboards},
+    profiles::{Profile, Profiles},
+    scoring::{
+        speed_bonus, Bonuses, DuplicateAnswers, ScoreAdjustment, ScoringContext, ScoringMethod,
+    },
+    tiles::TileSet,
+};
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 // Convert our custom Error type into HTTP responses
@@ -28,6 +47,26 @@ impl<'r> Responder<'r, 'r> for Error {
 
 pub(crate) type Player = String;
 
+pub(crate) type Team = String;
+
+/// The current time in milliseconds since the Unix epoch
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time after the Unix epoch")
+        .as_millis() as u64
+}
+
+/// Write saved statistics to their file so that they survive a restart
+pub(crate) fn write_stats(path: &str, json: &[u8]) {
+    // Replacing the file in one step means a crash never leaves half of it written
+    let temp_path = format!("{path}.tmp");
+    let result = fs::write(&temp_path, json).and_then(|()| fs::rename(&temp_path, path));
+    if let Err(error) = result {
+        eprintln!("could not save stats to {path}: {error}");
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub(crate) enum Error {
     GameConflict,
@@ -40,6 +79,27 @@ pub(crate) enum Error {
     WordUsesExtraLetters,
     InvalidGameSettings,
     WordMustBeAtLeastTwoLetters,
+    RackConstraintsUnsatisfiable,
+    LanguageNotFound,
+    InvalidBotSettings,
+    WordAlreadyPlayed,
+    TeamConflict,
+    TeamNotFound,
+    PlayerEliminated,
+    AlreadyAnswered,
+    WordBreaksRoundConstraint,
+    NoRerollsLeft,
+    HintsDisabled,
+    NoHintsLeft,
+    HintNotReady,
+    RoundNotComplete,
+    AnswerNotFound,
+    ChallengeNotFound,
+    ChallengeResolved,
+    ChallengeConflict,
+    GameIsReplay,
+    UnsupportedExportVersion,
+    InvalidExport,
 }
 
 impl fmt::Display for Error {
@@ -59,6 +119,34 @@ impl fmt::Display for Error {
             Self::WordMustBeAtLeastTwoLetters => {
                 write!(f, "word must be at least two letters long")
             }
+            Self::LanguageNotFound => write!(f, "language not found"),
+            Self::InvalidBotSettings => write!(f, "invalid bot settings"),
+            Self::WordAlreadyPlayed => write!(f, "word was already played this round"),
+            Self::TeamConflict => write!(f, "team conflict"),
+            Self::TeamNotFound => write!(f, "team not found"),
+            Self::PlayerEliminated => write!(f, "player was eliminated"),
+            Self::AlreadyAnswered => write!(f, "player already answered this round"),
+            Self::NoRerollsLeft => write!(f, "no rerolls left in this game"),
+            Self::HintsDisabled => write!(f, "hints are disabled in this game"),
+            Self::NoHintsLeft => write!(f, "no hints left for this round"),
+            Self::HintNotReady => write!(f, "hints are not ready yet, try again shortly"),
+            Self::RoundNotComplete => write!(f, "round is not complete"),
+            Self::AnswerNotFound => write!(f, "answer not found"),
+            Self::ChallengeNotFound => write!(f, "challenge not found"),
+            Self::ChallengeResolved => write!(f, "challenge was already resolved"),
+            Self::ChallengeConflict => write!(f, "answer was already challenged"),
+            Self::GameIsReplay => write!(f, "game is a read-only replay"),
+            Self::UnsupportedExportVersion => write!(f, "unsupported game export version"),
+            Self::InvalidExport => write!(f, "game export is not a valid game"),
+            Self::WordBreaksRoundConstraint => {
+                write!(f, "word does not follow the round's constraint")
+            }
+            Self::RackConstraintsUnsatisfiable => {
+                write!(
+                    f,
+                    "could not generate letters that satisfy the rack constraints"
+                )
+            }
         }
     }
 }
@@ -92,10 +180,39 @@ pub(crate) struct CreateGameData {
     pub(crate) settings: GameSettings,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AddBotData {
+    /// The name the bot plays under
+    pub(crate) player: Player,
+    /// How well and how quickly the bot plays
+    #[serde(default)]
+    pub(crate) bot: BotSettings,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChallengeData {
+    /// The player raising the challenge
+    pub(crate) player: Player,
+    /// The index of the round the answer was given in
+    pub(crate) round: usize,
+    /// The player whose answer is challenged
+    pub(crate) answer_player: Player,
+    /// The challenged word
+    pub(crate) answer: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct VoteData {
+    /// The player voting
+    pub(crate) player: Player,
+    /// Whether the player agrees with the challenge
+    pub(crate) agree: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub(crate) struct Answer {
     /// The player who gave the answer
-    player: Player,
+    pub(crate) player: Player,
     /// The word the player spelled for the round
     pub answer: String,
 }
@@ -103,16 +220,45 @@ pub(crate) struct Answer {
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub(crate) struct AnswerWithWordInfo {
     /// The player who gave the answer
-    player: Player,
+    pub(crate) player: Player,
     /// The word the player spelled for the round
     pub answer: String,
     /// The score of the word
     pub score: u32,
     /// The definition of the word
     pub definition: String,
+    /// The score of the word before any adjustments
+    #[serde(default)]
+    pub(crate) word_score: u32,
+    /// The changes made to the score once the round was complete
+    #[serde(default)]
+    pub(crate) adjustments: Vec<ScoreAdjustment>,
+    /// How long after the round started the answer was given, in milliseconds
+    #[serde(default)]
+    pub(crate) answer_time_ms: u64,
 }
 
-#[derive(PartialEq)]
+impl AnswerWithWordInfo {
+    /// Whether the answer counts as a playable word, after any challenge to it
+    pub(crate) fn is_accepted(&self) -> bool {
+        self.word_score > 0
+            && !self
+                .adjustments
+                .contains(&ScoreAdjustment::RejectedByChallenge)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) struct RejectedGuess {
+    /// The player who made the guess
+    pub(crate) player: Player,
+    /// The word that was not in the dictionary
+    pub(crate) guess: String,
+    /// How long after the round started the guess was made, in milliseconds
+    pub(crate) answer_time_ms: u64,
+}
+
+#[derive(Debug, PartialEq)]
 pub(crate) enum RoundState {
     Start,
     CollectingAnswers,
@@ -121,40 +267,224 @@ pub(crate) enum RoundState {
 
 #[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct Round {
+    /// A random number that tells the round apart from one it replaced or was replaced by
+    #[serde(default)]
+    pub(crate) id: u64,
     /// The list of letters that can be used to spell a word
-    pub(crate) letters: Vec<char>,
+    pub(crate) letters: Vec<String>,
+    /// How hard it is to find words with the letters
+    pub(crate) difficulty: Difficulty,
+    /// The bonus squares on the letters
+    #[serde(default)]
+    pub(crate) bonuses: Bonuses,
+    /// The rule every answer in the round must follow
+    #[serde(default)]
+    pub(crate) constraint: Option<RoundConstraint>,
     /// The list of answers given, one per player
     pub(crate) answers: Vec<AnswerWithWordInfo>,
+    /// The words each player found in a multi-word round
+    #[serde(default)]
+    pub(crate) word_lists: HashMap<Player, Vec<AnswerWithWordInfo>>,
+    /// When the round's clock started, in milliseconds since the Unix epoch, or unset until a
+    /// player starts it or answers
+    #[serde(default)]
+    pub(crate) started_at_ms: Option<u64>,
+    /// When a multi-word round stops taking words, in milliseconds since the Unix epoch, which
+    /// is set once its clock starts
+    #[serde(default)]
+    pub(crate) deadline_ms: Option<u64>,
     /// The number of guesses that a player has used
     pub(crate) guesses_used: HashMap<Player, u32>,
+    /// The players who voted to replace the letters
+    #[serde(default)]
+    pub(crate) reroll_votes: HashSet<Player>,
+    /// The number of hints each player has taken
+    #[serde(default)]
+    pub(crate) hints_used: HashMap<Player, u32>,
+    /// The guesses that were not in the dictionary
+    #[serde(default)]
+    pub(crate) rejected_guesses: Vec<RejectedGuess>,
     /// The list of best answers for this round
     pub(crate) best_answers: Vec<WordInfo>,
+    /// Whether the round's scores have been adjusted now that it is complete
+    #[serde(default)]
+    pub(crate) scored: bool,
 }
 
 impl Round {
-    fn new(letters: Vec<char>) -> Self {
+    fn new(rack: Rack) -> Self {
         Round {
-            letters: letters.clone(),
+            id: thread_rng().gen(),
+            letters: rack.letters,
+            difficulty: rack.difficulty,
+            bonuses: rack.bonuses,
+            constraint: rack.constraint,
             answers: Vec::new(),
+            word_lists: HashMap::new(),
+            started_at_ms: None,
+            deadline_ms: None,
             guesses_used: HashMap::new(),
+            reroll_votes: HashSet::new(),
+            hints_used: HashMap::new(),
+            rejected_guesses: Vec::new(),
             best_answers: Vec::new(),
+            scored: false,
+        }
+    }
+
+    /// The letters of the round along with their bonuses and constraint
+    pub(crate) fn rack(&self) -> Rack {
+        Rack {
+            letters: self.letters.clone(),
+            difficulty: self.difficulty,
+            bonuses: self.bonuses.clone(),
+            constraint: self.constraint.clone(),
+        }
+    }
+
+    /// How long the round's clock has been running, in milliseconds
+    fn time_since_start_ms(&self) -> u64 {
+        self.started_at_ms
+            .map_or(0, |started_at_ms| now_ms().saturating_sub(started_at_ms))
+    }
+
+    /// Every answer given in the round, whatever the game mode
+    pub(crate) fn all_answers(&self) -> impl Iterator<Item = &AnswerWithWordInfo> {
+        self.answers
+            .iter()
+            .chain(self.word_lists.values().flatten())
+    }
+
+    pub(crate) fn all_answers_mut(&mut self) -> impl Iterator<Item = &mut AnswerWithWordInfo> {
+        self.answers
+            .iter_mut()
+            .chain(self.word_lists.values_mut().flatten())
+    }
+
+    /// Adjust the score of every answer once the round is complete
+    fn score_answers(&mut self, settings: &GameSettings, dictionary: &Dictionary) {
+        let found_by = self.found_by(dictionary);
+        let best_score = self.best_score();
+        for answer in self.all_answers_mut() {
+            Self::adjust_score(answer, best_score, &found_by, settings, dictionary);
+        }
+    }
+
+    /// The players who gave each accepted word, keyed by the normalized word
+    fn found_by(&self, dictionary: &Dictionary) -> HashMap<String, Vec<Player>> {
+        let mut found_by: HashMap<String, Vec<Player>> = HashMap::new();
+        for answer in self.all_answers().filter(|answer| answer.is_accepted()) {
+            found_by
+                .entry(dictionary.normalize(&answer.answer))
+                .or_default()
+                .push(answer.player.clone());
         }
+        found_by
     }
 
-    fn state(&self, players: usize) -> RoundState {
+    /// Make the changes to an answer's score that depend on the rest of the complete round
+    fn adjust_score(
+        answer: &mut AnswerWithWordInfo,
+        best_score: u32,
+        found_by: &HashMap<String, Vec<Player>>,
+        settings: &GameSettings,
+        dictionary: &Dictionary,
+    ) {
+        if settings.percent_of_best && best_score > 0 && answer.score > 0 {
+            // House words and hidden offensive words can beat the best answer, but not 100%
+            answer.score = (100 * answer.score / best_score).min(100);
+            answer
+                .adjustments
+                .push(ScoreAdjustment::PercentOfBest { best_score });
+        }
+        let players = found_by
+            .get(&dictionary.normalize(&answer.answer))
+            .filter(|players| players.len() >= 2);
+        if let Some(players) = players {
+            let shared_with = players
+                .iter()
+                .filter(|player| **player != answer.player)
+                .cloned()
+                .collect();
+            match settings.duplicate_answers {
+                DuplicateAnswers::Keep => {}
+                DuplicateAnswers::Cancel => {
+                    answer.score = 0;
+                    answer
+                        .adjustments
+                        .push(ScoreAdjustment::Cancelled { shared_with });
+                }
+                DuplicateAnswers::Split => {
+                    answer.score /= players.len() as u32;
+                    answer
+                        .adjustments
+                        .push(ScoreAdjustment::Split { shared_with });
+                }
+            }
+        }
+        if settings.speed_bonus && answer.score > 0 {
+            let bonus = speed_bonus(answer.answer_time_ms);
+            if bonus > 0 {
+                answer.score += bonus;
+                answer
+                    .adjustments
+                    .push(ScoreAdjustment::SpeedBonus { bonus });
+            }
+        }
+    }
+
+    /// The score of the round's best word, or zero until the best words are found
+    fn best_score(&self) -> u32 {
+        self.best_answers.first().map_or(0, |word| word.score)
+    }
+
+    /// How close the player's best word came to the score of the round's best word, as a percent
+    pub(crate) fn percent_of_best(&self, player: &Player) -> Option<u32> {
+        let best_score = self.best_score();
+        let word_score = self
+            .all_answers()
+            .filter(|answer| answer.player == *player)
+            .map(|answer| answer.word_score)
+            .max()
+            .unwrap_or(0);
+        (100 * word_score)
+            .checked_div(best_score)
+            .map(|percent| percent.min(100))
+    }
+
+    /// The state of the round given the players still playing, whose answers are the only ones
+    /// that count towards it being complete
+    fn state(&self, players: &[&Player]) -> RoundState {
+        if let Some(deadline_ms) = self.deadline_ms {
+            return if now_ms() >= deadline_ms {
+                RoundState::Complete
+            } else if self.word_lists.is_empty() {
+                RoundState::Start
+            } else {
+                RoundState::CollectingAnswers
+            };
+        }
+        // Players eliminated or gone since answering leave answers behind that no longer count
+        let answers = self
+            .answers
+            .iter()
+            .filter(|answer| players.contains(&&answer.player))
+            .count();
         if self.answers.is_empty() {
             RoundState::Start
-        } else if self.answers.len() < players {
+        } else if answers < players.len() {
             RoundState::CollectingAnswers
-        } else if self.answers.len() == players {
-            RoundState::Complete
         } else {
-            panic!("Round in unknown state")
+            RoundState::Complete
         }
     }
 }
 
+/// The longest a multi-word round can last, which keeps its deadline from overflowing
+const MAX_ROUND_TIME_LIMIT_SECONDS: u64 = 24 * 60 * 60;
+
 #[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub(crate) struct GameSettings {
     /// The number of tiles to make words from
     pub(crate) number_of_tiles: u32,
@@ -163,7 +493,53 @@ pub(crate) struct GameSettings {
     /// The method to score words
     pub(crate) scoring_method: ScoringMethod,
     /// Letters that will not show up
-    pub(crate) banned_letters: HashSet<char>,
+    pub(crate) banned_letters: HashSet<String>,
+    /// Constraints every generated set of letters must satisfy
+    pub(crate) rack_constraints: RackConstraints,
+    /// The difficulty of the generated letters, or any difficulty if not set
+    pub(crate) difficulty: Option<Difficulty>,
+    /// The tiles to draw letters from, or the language's tile set if not set
+    pub(crate) tile_set: Option<TileSet>,
+    /// The language pack the word list, tiles and spelling rules come from
+    pub(crate) language: String,
+    /// Whether players give one word or as many words as they can each round
+    pub(crate) mode: GameMode,
+    /// How long players have to find words in a multi-word round
+    pub(crate) round_time_limit_seconds: u64,
+    /// How words found by more than one player in a round are scored
+    pub(crate) duplicate_answers: DuplicateAnswers,
+    /// Whether answers score a percentage of the best possible score for the round
+    pub(crate) percent_of_best: bool,
+    /// How the answers of a team's players combine into the team's score for a round
+    pub(crate) team_scoring: TeamScoring,
+    /// Whether the lowest scorer of each round is eliminated until one player remains
+    pub(crate) elimination: bool,
+    /// Whether correct answers earn bonus points for being given quickly
+    pub(crate) speed_bonus: bool,
+    /// Whether each round has a bonus letter square and a bonus word square
+    pub(crate) bonus_squares: bool,
+    /// Whether each round comes with a constraint that answers must follow
+    pub(crate) round_constraints: bool,
+    /// The number of times per game the players can vote to replace a round's letters
+    pub(crate) max_rerolls: u32,
+    /// Whether players can ask for hints about the best word
+    pub(crate) hints: bool,
+    /// The points taken off a player's round score for each hint
+    pub(crate) hint_penalty: u32,
+    /// Whether words accepted by a challenge are playable for the rest of the game
+    pub(crate) allow_challenged_words: bool,
+    /// Words playable in this game that are not in the word list, with their definitions
+    pub(crate) allowed_words: HashMap<String, String>,
+    /// Words in the word list that are not playable in this game
+    pub(crate) blocked_words: HashSet<String>,
+    /// Whether words flagged as offensive are hidden from best answers or not playable
+    pub(crate) offensive_words: OffensiveWordFilter,
+    /// The club whose leaderboard the game's results count towards
+    pub(crate) club: Option<String>,
+    /// Whether the game is listed in the lobby for anyone to join
+    pub(crate) public: bool,
+    /// The number of rounds after which the game is over, or play on until it is deleted if not set
+    pub(crate) max_rounds: Option<u32>,
 }
 
 impl Default for GameSettings {
@@ -171,29 +547,220 @@ impl Default for GameSettings {
         Self {
             number_of_tiles: 7,
             number_of_guesses: 2,
-            scoring_method: ScoringMethod::Normal,
+            scoring_method: ScoringMethod::default(),
             banned_letters: HashSet::new(),
+            rack_constraints: RackConstraints::default(),
+            difficulty: None,
+            tile_set: None,
+            language: DEFAULT_LANGUAGE.to_string(),
+            mode: GameMode::SingleWord,
+            round_time_limit_seconds: 120,
+            duplicate_answers: DuplicateAnswers::Keep,
+            percent_of_best: false,
+            team_scoring: TeamScoring::Sum,
+            elimination: false,
+            speed_bonus: false,
+            bonus_squares: false,
+            round_constraints: false,
+            max_rerolls: 1,
+            hints: true,
+            hint_penalty: 2,
+            allow_challenged_words: false,
+            allowed_words: HashMap::new(),
+            blocked_words: HashSet::new(),
+            offensive_words: OffensiveWordFilter::Off,
+            club: None,
+            public: false,
+            max_rounds: None,
         }
     }
 }
 
 impl GameSettings {
-    fn is_valid(&self) -> bool {
-        self.number_of_tiles >= 2 && self.number_of_guesses >= 1
+    /// Whether a player asking for these settings would be happy to play a game with the others
+    fn is_compatible(&self, other: &GameSettings) -> bool {
+        self.language == other.language
+            && self.mode == other.mode
+            && self.number_of_tiles == other.number_of_tiles
+            && self.scoring_method == other.scoring_method
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.number_of_tiles >= 2
+            && self.number_of_guesses >= 1
+            && (1..=MAX_ROUND_TIME_LIMIT_SECONDS).contains(&self.round_time_limit_seconds)
+            && self.max_rounds != Some(0)
+            && self.rack_constraints.is_valid(self.number_of_tiles)
+            && self
+                .tile_set
+                .as_ref()
+                .is_none_or(|tile_set| tile_set.is_valid(self.number_of_tiles))
+    }
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct RackConstraints {
+    /// The minimum number of vowels in the letters
+    pub(crate) min_vowels: Option<u32>,
+    /// The maximum number of vowels in the letters
+    pub(crate) max_vowels: Option<u32>,
+    /// The minimum length of the longest playable word
+    pub(crate) min_best_word_length: Option<u32>,
+    /// Whether there must be a playable word that uses every letter
+    pub(crate) require_bingo: bool,
+    /// The maximum number of letters that repeat a letter already in the set
+    pub(crate) max_duplicate_letters: Option<u32>,
+}
+
+impl RackConstraints {
+    fn is_valid(&self, number_of_tiles: u32) -> bool {
+        let min_vowels = self.min_vowels.unwrap_or(0);
+        let max_vowels = self.max_vowels.unwrap_or(number_of_tiles);
+        min_vowels <= max_vowels
+            && min_vowels <= number_of_tiles
+            && self.min_best_word_length.unwrap_or(0) <= number_of_tiles
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
-pub(crate) enum ScoringMethod {
-    Normal,
-    Length,
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub(crate) enum GameMode {
+    /// Each player gives one word per round
+    SingleWord,
+    /// Each player gives as many words as they can before the round's time limit
+    MultiWord,
 }
 
-impl ScoringMethod {
-    pub(crate) fn score(&self, word_info: &WordInfo) -> u32 {
+impl GameMode {
+    /// How many of the best words to show after a round
+    pub(crate) fn best_answer_count(&self) -> usize {
         match self {
-            ScoringMethod::Normal => word_info.score,
-            ScoringMethod::Length => word_info.word.len() as u32,
+            GameMode::SingleWord => 5,
+            GameMode::MultiWord => usize::MAX,
+        }
+    }
+}
+
+/// A rule that every answer in a round must follow
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub(crate) enum RoundConstraint {
+    /// The word must use this letter
+    MustUseLetter { letter: String },
+    /// The word must start with this letter
+    MustStartWith { letter: String },
+    /// The word must use at least this many tiles
+    MinimumLength { length: usize },
+    /// The word must use the highlighted tile at this index in the letters
+    MustUseTile { index: usize },
+}
+
+impl RoundConstraint {
+    /// The length used for minimum length constraints when the rack is long enough
+    pub(crate) const MINIMUM_LENGTH: usize = 5;
+
+    pub(crate) fn is_met(&self, letters: &[String], tiles: &[String]) -> bool {
+        match self {
+            Self::MustUseLetter { letter } => tiles.contains(letter),
+            Self::MustStartWith { letter } => tiles.first() == Some(letter),
+            Self::MinimumLength { length } => tiles.len() >= *length,
+            Self::MustUseTile { index } => {
+                if *index >= letters.len() {
+                    return false;
+                }
+                // Spell the word trying the highlighted tile first, so that another tile with the
+                // same letter does not take its place
+                let rotated: Vec<String> = letters[*index..]
+                    .iter()
+                    .chain(&letters[..*index])
+                    .cloned()
+                    .collect();
+                Dictionary::check_word_uses_letters(&rotated, tiles)
+                    .is_some_and(|used| used.contains(&0))
+            }
+        }
+    }
+}
+
+/// Something revealed about the round's best word, each hint revealing more
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) enum Hint {
+    /// How many letters the word has
+    Length { length: usize },
+    /// The letter the word starts with
+    FirstLetter { letter: String },
+    /// The start of the word's definition
+    Definition { snippet: String },
+}
+
+impl Hint {
+    /// The number of characters of the definition that are revealed
+    const DEFINITION_SNIPPET_LENGTH: usize = 40;
+    /// The number of hints there are for each round
+    const COUNT: u32 = 3;
+
+    fn new(level: u32, word: &WordInfo) -> Self {
+        match level {
+            0 => Hint::Length {
+                length: word.tiles.len(),
+            },
+            1 => Hint::FirstLetter {
+                letter: word.tiles.first().cloned().unwrap_or_default(),
+            },
+            _ => Hint::Definition {
+                snippet: word
+                    .definition
+                    .chars()
+                    .take(Self::DEFINITION_SNIPPET_LENGTH)
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub(crate) enum TeamScoring {
+    /// The team scores the total of its players' scores
+    Sum,
+    /// The team scores its best player's score
+    Best,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Scores {
+    /// The total score of each player
+    pub(crate) players: HashMap<Player, u32>,
+    /// The total score of each team
+    pub(crate) teams: HashMap<Team, u32>,
+    /// The players of an elimination game from first to last place
+    pub(crate) ranking: Vec<Player>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub(crate) enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Racks with at least this many playable words per tile are easy
+    const EASY_WORDS_PER_TILE: f64 = 6.0;
+    /// Racks with fewer than this many playable words per tile are hard
+    const HARD_WORDS_PER_TILE: f64 = 2.0;
+
+    /// The number of playable words that makes a rack with this many tiles easy
+    pub(crate) fn easy_word_count(number_of_tiles: usize) -> usize {
+        (Self::EASY_WORDS_PER_TILE * number_of_tiles.max(1) as f64).ceil() as usize
+    }
+
+    pub(crate) fn from_playable_words(playable_words: usize, number_of_tiles: usize) -> Self {
+        let words_per_tile = playable_words as f64 / number_of_tiles.max(1) as f64;
+        if words_per_tile >= Self::EASY_WORDS_PER_TILE {
+            Difficulty::Easy
+        } else if words_per_tile < Self::HARD_WORDS_PER_TILE {
+            Difficulty::Hard
+        } else {
+            Difficulty::Medium
         }
     }
 }
@@ -206,19 +773,72 @@ pub(crate) struct Game {
     pub(crate) rounds: Vec<Round>,
     /// The settings for the game
     pub(crate) settings: GameSettings,
+    /// The players that are bots, which are also in the list of players
+    #[serde(default)]
+    pub(crate) bots: HashMap<Player, BotSettings>,
+    /// The players on each team
+    #[serde(default)]
+    pub(crate) teams: HashMap<Team, HashSet<Player>>,
+    /// The players knocked out of an elimination game, in the order they were eliminated
+    #[serde(default)]
+    pub(crate) eliminated: Vec<Player>,
+    /// The number of times the letters have been replaced by a vote
+    #[serde(default)]
+    pub(crate) rerolls_used: u32,
+    /// Every challenge raised in the game, including resolved ones
+    #[serde(default)]
+    pub(crate) challenges: Vec<Challenge>,
+    /// Words accepted by a challenge that are playable for the rest of the game
+    #[serde(default)]
+    pub(crate) house_words: HashSet<String>,
+    /// Whether the game was imported from an export and can only be viewed
+    #[serde(default)]
+    pub(crate) replay: bool,
+    /// Every change made to the game, in the order it was made
+    #[serde(skip)]
+    pub(crate) events: Vec<GameEvent>,
+}
+
+/// A self-contained record of a game that can be archived and imported again
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GameExport {
+    /// The version of the export format
+    pub(crate) version: u32,
+    /// When the game was exported, in milliseconds since the Unix epoch
+    pub(crate) exported_at_ms: u64,
+    /// Every change made to the game, from its settings to the last answer
+    pub(crate) events: Vec<GameEvent>,
+}
+
+impl GameExport {
+    /// The export format version this server writes and can import
+    pub(crate) const VERSION: u32 = 2;
+
+    pub(crate) fn new(game: &Game) -> Self {
+        Self {
+            version: Self::VERSION,
+            exported_at_ms: now_ms(),
+            events: game.events.clone(),
+        }
+    }
 }
 
 impl Game {
     pub(crate) fn add_player(&mut self, player: Player) -> Result<()> {
+        self.check_can_join(&player)?;
+        self.apply(GameEvent::PlayerJoined { player });
+        Ok(())
+    }
+
+    fn check_can_join(&self, player: &Player) -> Result<()> {
         // Only allow adding players at the start of a round
         if self.current_round_state() != RoundState::Start {
             return Err(Error::RoundNotInStartState);
         }
-        if self.players.insert(player) {
-            Ok(())
-        } else {
-            Err(Error::PlayerConflict)
+        if self.players.contains(player) {
+            return Err(Error::PlayerConflict);
         }
+        Ok(())
     }
 
     pub(crate) fn remove_player(&mut self, player: Player) -> Result<()> {
@@ -226,85 +846,454 @@ impl Game {
         if self.current_round_state() != RoundState::Start {
             return Err(Error::RoundNotInStartState);
         }
-        self.players.remove(&player);
+        self.apply(GameEvent::PlayerLeft { player });
         Ok(())
     }
 
+    pub(crate) fn create_team(&mut self, team: Team) -> Result<()> {
+        // Only allow changing teams at the start of a round
+        if self.current_round_state() != RoundState::Start {
+            return Err(Error::RoundNotInStartState);
+        }
+        if self.teams.contains_key(&team) {
+            return Err(Error::TeamConflict);
+        }
+        self.apply(GameEvent::TeamCreated { team });
+        Ok(())
+    }
+
+    pub(crate) fn join_team(&mut self, team: Team, player: Player) -> Result<()> {
+        // Only allow changing teams at the start of a round
+        if self.current_round_state() != RoundState::Start {
+            return Err(Error::RoundNotInStartState);
+        }
+        if !self.players.contains(&player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if !self.teams.contains_key(&team) {
+            return Err(Error::TeamNotFound);
+        }
+        self.apply(GameEvent::TeamJoined { team, player });
+        Ok(())
+    }
+
+    pub(crate) fn add_bot(&mut self, player: Player, bot: BotSettings) -> Result<()> {
+        if !bot.is_valid() {
+            return Err(Error::InvalidBotSettings);
+        }
+        self.check_can_join(&player)?;
+        self.apply(GameEvent::BotAdded { player, bot });
+        Ok(())
+    }
+
+    /// Record a vote to replace the letters, returning whether a majority now agrees
+    pub(crate) fn vote_reroll(&mut self, player: Player) -> Result<bool> {
+        if !self.players.contains(&player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if self.eliminated.contains(&player) {
+            return Err(Error::PlayerEliminated);
+        }
+        // The letters can only be replaced before anyone answers
+        if self.current_round_state() != RoundState::Start {
+            return Err(Error::RoundNotInStartState);
+        }
+        if self.rerolls_used >= self.settings.max_rerolls {
+            return Err(Error::NoRerollsLeft);
+        }
+        self.apply(GameEvent::RerollVoted { player });
+        let votes = self.current_round().reroll_votes.len();
+        Ok(votes * 2 > self.active_players().count())
+    }
+
+    /// Replace the current round with one using new letters
+    pub(crate) fn reroll(&mut self, rack: Rack) {
+        let round = Round::new(rack);
+        self.apply(GameEvent::RoundRerolled { round });
+    }
+
+    /// Start the current round's clock for a player who is ready to play
+    pub(crate) fn start(&mut self, player: Player) -> Result<bool> {
+        if !self.players.contains(&player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if self.eliminated.contains(&player) {
+            return Err(Error::PlayerEliminated);
+        }
+        if self.current_round_state() == RoundState::Complete {
+            return Err(Error::RoundNotInCollectingAnswersState);
+        }
+        Ok(self.start_clock())
+    }
+
+    /// Start the current round's clock, returning whether it was not already running
+    fn start_clock(&mut self) -> bool {
+        if self.clock_started() {
+            return false;
+        }
+        self.apply(GameEvent::ClockStarted {
+            started_at_ms: now_ms(),
+        });
+        true
+    }
+
+    /// Whether players have started playing the current round
+    pub(crate) fn clock_started(&self) -> bool {
+        self.current_round().started_at_ms.is_some()
+    }
+
+    /// Stop the clock of a timed round that ran out without any answers
+    pub(crate) fn stop_clock(&mut self) {
+        self.apply(GameEvent::ClockStopped);
+    }
+
+    /// Reveal the next hint about the round's best word, at a cost to the player's score
+    pub(crate) fn hint(&mut self, player: Player) -> Result<Hint> {
+        if !self.settings.hints {
+            return Err(Error::HintsDisabled);
+        }
+        if !self.players.contains(&player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if self.eliminated.contains(&player) {
+            return Err(Error::PlayerEliminated);
+        }
+        if self.current_round_state() == RoundState::Complete {
+            return Err(Error::RoundNotInCollectingAnswersState);
+        }
+        // A hint is no use once the player has answered, so it should not cost them points
+        if self
+            .current_round()
+            .all_answers()
+            .any(|answer| answer.player == player)
+        {
+            return Err(Error::AlreadyAnswered);
+        }
+        // The best words are searched for in the background, which is too slow to wait for here
+        let round = self.current_round();
+        let hints_used = round.hints_used.get(&player).copied().unwrap_or(0);
+        let Some(best_word) = round.best_answers.first() else {
+            return Err(Error::HintNotReady);
+        };
+        if hints_used >= Hint::COUNT {
+            return Err(Error::NoHintsLeft);
+        }
+        let hint = Hint::new(hints_used, best_word);
+        self.apply(GameEvent::HintGiven { player });
+        Ok(hint)
+    }
+
     pub(crate) fn answer(&mut self, answer: Answer, dictionary: &Dictionary) -> Result<()> {
         let player = &answer.player;
         // Confirm the player exists
         if !self.players.contains(player) {
             return Err(Error::PlayerNotFound);
         }
+        // Eliminated players only watch
+        if self.eliminated.contains(player) {
+            return Err(Error::PlayerEliminated);
+        }
         // Confirm we are collecting answers for the current round
         let state = self.current_round_state();
         if state != RoundState::Start && self.current_round_state() != RoundState::CollectingAnswers
         {
             return Err(Error::RoundNotInCollectingAnswersState);
         }
+        // Clients start the clock when they show the round, or else the first answer starts it
+        self.start_clock();
 
-        let number_of_guesses = self.settings.number_of_guesses;
-        let scoring_method = self.settings.scoring_method.clone();
-        let round = self.current_round_mut();
+        let word_info = self.playable_word_info(&answer.answer, dictionary);
+        let scoring_method = self.settings.scoring_method;
+        let multi_word = self.settings.mode == GameMode::MultiWord;
+        let tile_set = dictionary.tile_set(&self.settings);
+        let round = self.current_round();
+        let answer_time_ms = round.time_since_start_ms();
         // Check if this player already added an answer
         for a in &round.answers {
             if a.player == answer.player {
                 return Ok(());
             }
         }
-        // Check that the word is at least 2 letters long
-        if answer.answer.len() < 2 {
-            return Err(Error::WordMustBeAtLeastTwoLetters);
-        }
+        let word = dictionary.normalize(&answer.answer);
+        // Spell the word with the game's tiles, which may have more than one letter each and
+        // overlap, so there can be more than one way to spell it
+        let spellings: Vec<Vec<String>> = tile_set
+            .tokenizations(&word)
+            .into_iter()
+            .filter(|tiles| Dictionary::check_word_uses_letters(&round.letters, tiles).is_some())
+            .collect();
         // Check that the word is valid with the letters from this round
-        if !Dictionary::check_word_uses_letters(&round.letters, &answer.answer) {
+        if spellings.is_empty() {
             return Err(Error::WordUsesExtraLetters);
         }
+        // Check that the word is at least 2 tiles long
+        if spellings.iter().all(|tiles| tiles.len() < 2) {
+            return Err(Error::WordMustBeAtLeastTwoLetters);
+        }
+        // Check that the word follows the round's constraint
+        let Some(tiles) = spellings
+            .into_iter()
+            .filter(|tiles| tiles.len() >= 2)
+            .find(|tiles| {
+                round
+                    .constraint
+                    .as_ref()
+                    .is_none_or(|constraint| constraint.is_met(&round.letters, tiles))
+            })
+        else {
+            return Err(Error::WordBreaksRoundConstraint);
+        };
         // Check if the word is playable
-        match dictionary.get_word_info_if_playable(&answer.answer) {
-            Some(word_info) => {
-                let score = scoring_method.score(word_info);
-
-                let answer_with_info = AnswerWithWordInfo {
-                    player: answer.player,
-                    answer: answer.answer,
-                    score,
-                    definition: word_info.definition.clone(),
-                };
-                // Add the answer with info
-                round.answers.push(answer_with_info);
+        let Some(mut word_info) = word_info else {
+            let guess = RejectedGuess {
+                player: answer.player.clone(),
+                guess: answer.answer,
+                answer_time_ms,
+            };
+            self.apply(GameEvent::GuessRejected { guess });
+            // Running out of guesses forfeits the round with an empty answer
+            let forfeited = self
+                .current_round()
+                .answers
+                .iter()
+                .any(|a| a.player == answer.player);
+            return if forfeited {
                 Ok(())
-            }
-            None => {
-                let guesses_used = round.guesses_used.entry(player.clone()).or_default();
-                *guesses_used += 1;
-                if *guesses_used == number_of_guesses {
-                    let empty_answer = AnswerWithWordInfo {
-                        player: answer.player,
-                        answer: answer.answer,
-                        score: 0,
-                        definition: String::from(""),
-                    };
-                    round.answers.push(empty_answer);
-                    Ok(())
-                } else {
-                    Err(Error::WordNotInDictionary)
-                }
-            }
+            } else {
+                Err(Error::WordNotInDictionary)
+            };
+        };
+        if multi_word
+            && round
+                .word_lists
+                .get(player)
+                .into_iter()
+                .flatten()
+                .any(|a| dictionary.normalize(&a.answer) == word)
+        {
+            return Err(Error::WordAlreadyPlayed);
         }
+        // Score the tiles the word was spelled with this round
+        word_info.tiles = tiles;
+        let context = ScoringContext {
+            letters: &round.letters,
+            tile_set,
+            bonuses: &round.bonuses,
+        };
+        let score = scoring_method.score(&word_info, &context);
+        let answer = AnswerWithWordInfo {
+            player: answer.player,
+            answer: answer.answer,
+            score,
+            definition: word_info.definition,
+            word_score: score,
+            adjustments: Vec::new(),
+            answer_time_ms,
+        };
+        self.apply(GameEvent::AnswerAccepted { answer });
+        Ok(())
     }
 
-    pub(crate) fn add_round_if_complete(&mut self, letters: Vec<char>) -> bool {
+    /// Give up on the current round with an answer that scores nothing
+    pub(crate) fn forfeit(&mut self, player: Player) -> Result<()> {
+        if !self.players.contains(&player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if self.eliminated.contains(&player) {
+            return Err(Error::PlayerEliminated);
+        }
         if self.current_round_state() == RoundState::Complete {
-            self.add_round(letters);
-            true
+            return Err(Error::RoundNotInCollectingAnswersState);
+        }
+        // Multi-word rounds end at their deadline whether or not everyone found a word
+        if self.settings.mode == GameMode::MultiWord
+            || self
+                .current_round()
+                .answers
+                .iter()
+                .any(|a| a.player == player)
+        {
+            return Ok(());
+        }
+        let answer = AnswerWithWordInfo {
+            player,
+            answer: String::new(),
+            score: 0,
+            definition: String::new(),
+            word_score: 0,
+            adjustments: Vec::new(),
+            answer_time_ms: self.current_round().time_since_start_ms(),
+        };
+        self.apply(GameEvent::AnswerAccepted { answer });
+        Ok(())
+    }
+
+    /// The info for a word if the game accepts it, checking the game's own words first
+    fn playable_word_info(&self, word: &str, dictionary: &Dictionary) -> Option<WordInfo> {
+        if self.house_words.contains(&dictionary.normalize(word)) {
+            return dictionary.unlisted_word_info(word, &self.settings);
+        }
+        dictionary
+            .get_game_word_info(word, &self.settings)
+            .map(Cow::into_owned)
+    }
+
+    /// Ask the players to overrule the dictionary on an answer from a completed round
+    pub(crate) fn challenge(
+        &mut self,
+        challenge: ChallengeData,
+        dictionary: &Dictionary,
+    ) -> Result<usize> {
+        if !self.players.contains(&challenge.player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if self.eliminated.contains(&challenge.player) {
+            return Err(Error::PlayerEliminated);
+        }
+        // A challenge rescores the answer, so the round's own scoring must be done first
+        if self
+            .rounds
+            .get(challenge.round)
+            .is_some_and(|round| !round.scored)
+        {
+            return Err(Error::RoundNotComplete);
+        }
+        let word = dictionary.normalize(&challenge.answer);
+        // An answer is only put to the vote once
+        if self.challenges.iter().any(|c| {
+            c.round == challenge.round
+                && c.player == challenge.answer_player
+                && dictionary.normalize(&c.answer) == word
+        }) {
+            return Err(Error::ChallengeConflict);
+        }
+        let answer = self
+            .rounds
+            .get(challenge.round)
+            .and_then(|round| {
+                round.all_answers().find(|a| {
+                    a.player == challenge.answer_player && dictionary.normalize(&a.answer) == word
+                })
+            })
+            .ok_or(Error::AnswerNotFound)?;
+        let kind = if answer.is_accepted() {
+            ChallengeKind::Reject
         } else {
-            false
+            ChallengeKind::Accept
+        };
+        let challenge = Challenge {
+            round: challenge.round,
+            player: challenge.answer_player,
+            answer: answer.answer.clone(),
+            kind,
+            challenger: challenge.player.clone(),
+            votes: HashMap::from([(challenge.player, true)]),
+            outcome: None,
+        };
+        self.apply(GameEvent::ChallengeRaised { challenge });
+        let id = self.challenges.len() - 1;
+        self.resolve_challenge(id, dictionary);
+        Ok(id)
+    }
+
+    pub(crate) fn vote_on_challenge(
+        &mut self,
+        id: usize,
+        vote: VoteData,
+        dictionary: &Dictionary,
+    ) -> Result<()> {
+        if !self.players.contains(&vote.player) {
+            return Err(Error::PlayerNotFound);
+        }
+        if self.eliminated.contains(&vote.player) {
+            return Err(Error::PlayerEliminated);
+        }
+        let challenge = self.challenges.get(id).ok_or(Error::ChallengeNotFound)?;
+        if challenge.outcome.is_some() {
+            return Err(Error::ChallengeResolved);
+        }
+        self.apply(GameEvent::ChallengeVoted {
+            id,
+            player: vote.player,
+            agree: vote.agree,
+        });
+        self.resolve_challenge(id, dictionary);
+        Ok(())
+    }
+
+    /// Change the challenged answer's score once a majority agrees with the challenge
+    fn resolve_challenge(&mut self, id: usize, dictionary: &Dictionary) {
+        // Bots never vote, so only the players still in the game count towards a majority
+        let voters = self
+            .active_players()
+            .filter(|player| !self.bots.contains_key(*player))
+            .count();
+        let challenge = &self.challenges[id];
+        let Some(outcome) = challenge.tally(voters) else {
+            return;
+        };
+        let mut answer = None;
+        let mut house_word = None;
+        let round = &self.rounds[challenge.round];
+        let challenged_answer = round
+            .all_answers()
+            .find(|a| a.player == challenge.player && a.answer == challenge.answer);
+        if let (ChallengeOutcome::Upheld, Some(challenged_answer)) = (outcome, challenged_answer) {
+            let mut rescored = challenged_answer.clone();
+            match challenge.kind {
+                ChallengeKind::Reject => {
+                    rescored.score = 0;
+                    rescored
+                        .adjustments
+                        .push(ScoreAdjustment::RejectedByChallenge);
+                }
+                ChallengeKind::Accept => {
+                    let context = ScoringContext {
+                        letters: &round.letters,
+                        tile_set: dictionary.tile_set(&self.settings),
+                        bonuses: &round.bonuses,
+                    };
+                    rescored.word_score = dictionary
+                        .unlisted_word_info(&challenge.answer, &self.settings)
+                        .map_or(0, |word_info| {
+                            self.settings.scoring_method.score(&word_info, &context)
+                        });
+                    rescored.score = rescored.word_score;
+                    rescored.adjustments.clear();
+                    // Score the answer as if the dictionary had accepted it in the first place
+                    let mut found_by = round.found_by(dictionary);
+                    found_by
+                        .entry(dictionary.normalize(&rescored.answer))
+                        .or_default()
+                        .push(rescored.player.clone());
+                    Round::adjust_score(
+                        &mut rescored,
+                        round.best_score(),
+                        &found_by,
+                        &self.settings,
+                        dictionary,
+                    );
+                    rescored
+                        .adjustments
+                        .push(ScoreAdjustment::AcceptedByChallenge);
+                    if self.settings.allow_challenged_words {
+                        house_word = Some(dictionary.normalize(&challenge.answer));
+                    }
+                }
+            }
+            answer = Some(rescored);
         }
+        self.apply(GameEvent::ChallengeResolved {
+            id,
+            outcome,
+            answer,
+            house_word,
+        });
     }
 
-    fn add_round(&mut self, letters: Vec<char>) {
-        self.rounds.push(Round::new(letters));
+    pub(crate) fn add_round(&mut self, rack: Rack) {
+        let round = Round::new(rack);
+        self.apply(GameEvent::RoundStarted { round });
     }
 
     pub(crate) fn current_round(&self) -> &Round {
@@ -312,78 +1301,411 @@ impl Game {
         &self.rounds[index]
     }
 
-    fn current_round_mut(&mut self) -> &mut Round {
+    pub(crate) fn current_round_mut(&mut self) -> &mut Round {
         let index = self.rounds.len() - 1;
         &mut self.rounds[index]
     }
 
-    fn current_round_state(&self) -> RoundState {
-        let players = self.players.len();
+    pub(crate) fn current_round_state(&self) -> RoundState {
+        let players: Vec<&Player> = self.active_players().collect();
+        self.current_round().state(&players)
+    }
+
+    /// The players who have not been eliminated
+    pub(crate) fn active_players(&self) -> impl Iterator<Item = &Player> {
+        self.players
+            .iter()
+            .filter(|player| !self.eliminated.contains(player))
+    }
+
+    /// Whether the game is public and waiting for players to join
+    fn is_open(&self) -> bool {
+        self.settings.public
+            && !self.replay
+            && !self.is_over()
+            && self.current_round_state() == RoundState::Start
+    }
+
+    /// Whether an elimination game is down to its last player, or the game has played all its rounds
+    pub(crate) fn is_over(&self) -> bool {
+        let last_player_standing = self.settings.elimination
+            && !self.eliminated.is_empty()
+            && self.active_players().count() <= 1;
+        let all_rounds_played = self
+            .settings
+            .max_rounds
+            .is_some_and(|max_rounds| self.scored_rounds().count() >= max_rounds as usize);
+        last_player_standing || all_rounds_played
+    }
+
+    /// The rounds that every answer is in for and have been scored
+    fn scored_rounds(&self) -> impl Iterator<Item = &Round> {
+        self.rounds.iter().filter(|round| round.scored)
+    }
+
+    /// Adjust the scores of the current round once every answer is in
+    pub(crate) fn score_round(&mut self, dictionary: &Dictionary) {
+        if self.settings.percent_of_best {
+            // Finishing a round finds the best answers off the lock, so this is only a fallback
+            self.find_best_answers(dictionary);
+        }
+        let mut round = self.current_round().clone();
+        round.score_answers(&self.settings, dictionary);
+        self.apply(GameEvent::RoundScored {
+            answers: round.answers,
+            word_lists: round.word_lists,
+        });
+        if self.settings.elimination {
+            self.eliminate_lowest_scorer();
+        }
+    }
+
+    /// Knock out the player whose answers have the lowest total `score`, which includes the
+    /// scoring adjustments but not hint penalties
+    fn eliminate_lowest_scorer(&mut self) {
+        if self.active_players().count() < 2 {
+            return;
+        }
         let round = self.current_round();
-        round.state(players)
+        // Lowest score goes first, then the shortest word, then the latest answer
+        let eliminated = self
+            .active_players()
+            .min_by_key(|player| {
+                let answers = || round.all_answers().filter(|a| a.player == **player);
+                let score: u32 = answers().map(|a| a.score).sum();
+                let longest_word = answers().map(|a| a.answer.chars().count()).max();
+                let submitted = answers().map(|a| a.answer_time_ms).min();
+                (
+                    score,
+                    longest_word.unwrap_or(0),
+                    Reverse(submitted.unwrap_or(u64::MAX)),
+                    (*player).clone(),
+                )
+            })
+            .cloned();
+        if let Some(player) = eliminated {
+            self.apply(GameEvent::PlayerEliminated { player });
+        }
     }
 
-    pub fn get_score(
-        &self,
-        dictionary: &Dictionary,
-        scoring_method: &ScoringMethod,
-    ) -> HashMap<String, u32> {
-        let mut scores = HashMap::new();
-        for round in &self.rounds {
-            for answer in round.answers.iter() {
-                let score = scores.entry(answer.player.clone()).or_insert(0);
-                if let Some(word_info) = dictionary.get_word_info_if_playable(&answer.answer) {
-                    *score += scoring_method.score(word_info);
-                }
+    /// Find the current round's best answers if the background search has not finished
+    pub(crate) fn find_best_answers(&mut self, dictionary: &Dictionary) {
+        let round = self.current_round();
+        if !round.best_answers.is_empty() {
+            return;
+        }
+        let best_answers = dictionary.get_best_words_now(
+            &round.rack(),
+            self.settings.mode.best_answer_count(),
+            &self.settings,
+        );
+        self.set_best_answers(self.rounds.len() - 1, best_answers);
+    }
+
+    /// Record the best answers found for a round
+    pub(crate) fn set_best_answers(&mut self, round: usize, best_answers: Vec<WordInfo>) {
+        self.apply(GameEvent::BestAnswersFound {
+            round,
+            best_answers,
+        });
+    }
+
+    /// Each player's score for a round of the game, after hint penalties
+    pub(crate) fn round_scores<'a>(&self, round: &'a Round) -> HashMap<&'a Player, u32> {
+        let mut round_scores: HashMap<&Player, u32> = HashMap::new();
+        for answer in round.all_answers() {
+            *round_scores.entry(&answer.player).or_insert(0) += answer.score;
+        }
+        for (player, hints) in &round.hints_used {
+            let round_score = round_scores.entry(player).or_insert(0);
+            *round_score =
+                round_score.saturating_sub(hints.saturating_mul(self.settings.hint_penalty));
+        }
+        round_scores
+    }
+
+    pub fn get_score(&self) -> Scores {
+        self.score_rounds(self.rounds.iter())
+    }
+
+    /// The scores of the rounds that have been scored, leaving out a round still being played
+    pub(crate) fn get_final_score(&self) -> Scores {
+        self.score_rounds(self.scored_rounds())
+    }
+
+    fn score_rounds<'a>(&'a self, rounds: impl Iterator<Item = &'a Round>) -> Scores {
+        let mut scores = Scores {
+            players: HashMap::new(),
+            teams: self.teams.keys().map(|team| (team.clone(), 0)).collect(),
+            ranking: Vec::new(),
+        };
+        for round in rounds {
+            let round_scores = self.round_scores(round);
+            for (player, round_score) in &round_scores {
+                *scores.players.entry((*player).clone()).or_insert(0) += round_score;
+            }
+            for (team, players) in &self.teams {
+                let player_scores = players.iter().filter_map(|p| round_scores.get(p));
+                let team_score = match self.settings.team_scoring {
+                    TeamScoring::Sum => player_scores.sum(),
+                    TeamScoring::Best => player_scores.max().copied().unwrap_or(0),
+                };
+                *scores.teams.entry(team.clone()).or_insert(0) += team_score;
             }
         }
+        if self.settings.elimination {
+            // The last players eliminated place highest
+            let mut active: Vec<Player> = self.active_players().cloned().collect();
+            active.sort_by_key(|player| Reverse(scores.players.get(player).copied()));
+            scores.ranking = active;
+            scores.ranking.extend(self.eliminated.iter().rev().cloned());
+        }
         scores
     }
 }
 
+/// The length of the game IDs made up for games created by quick match
+const GAME_ID_LENGTH: usize = 6;
+
+/// A public game waiting for players, as shown in the lobby with only the settings a player
+/// needs to decide whether to join
+#[derive(Serialize)]
+pub(crate) struct GameListing {
+    pub(crate) game_id: String,
+    /// The number of players in the game
+    pub(crate) players: usize,
+    pub(crate) language: String,
+    pub(crate) mode: GameMode,
+    pub(crate) number_of_tiles: u32,
+    pub(crate) scoring_method: ScoringMethod,
+    pub(crate) round_time_limit_seconds: u64,
+    pub(crate) max_rounds: Option<u32>,
+    pub(crate) club: Option<String>,
+}
+
+/// The statistics kept across games, which outlive the games they come from
+#[derive(Default, Deserialize, Serialize)]
+struct Stats {
+    /// Every player's statistics and rating across the games they have played
+    profiles: Profiles,
+    leaderboards: Leaderboards,
+}
+
 #[derive(Default)]
-pub(crate) struct Games(HashMap<String, Game>);
+pub(crate) struct Games {
+    games: HashMap<String, Game>,
+    stats: Stats,
+    /// The file the statistics are saved to, if they are kept
+    stats_path: Option<String>,
+    /// Whether the statistics have changed since they were last saved
+    stats_changed: bool,
+}
 
 impl Games {
+    /// Keep the statistics in a file, starting from the ones already saved in it
+    pub(crate) fn with_stats_file(path: String) -> Self {
+        let stats = match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)).unwrap(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Stats::default(),
+            Err(error) => panic!("could not read stats from {path}: {error}"),
+        };
+        Self {
+            stats,
+            stats_path: Some(path),
+            ..Default::default()
+        }
+    }
+
+    /// Mark the statistics to be written to their file the next time they are saved
+    fn save_stats(&mut self) {
+        self.stats_changed = self.stats_path.is_some();
+    }
+
+    /// The file to save the statistics to and their contents, if they changed since last time,
+    /// so that they can be written without holding the lock on the games
+    pub(crate) fn take_stats_to_save(&mut self) -> Option<(String, Vec<u8>)> {
+        if !std::mem::take(&mut self.stats_changed) {
+            return None;
+        }
+        let path = self.stats_path.clone()?;
+        match serde_json::to_vec(&self.stats) {
+            Ok(json) => Some((path, json)),
+            Err(error) => {
+                eprintln!("could not save stats to {path}: {error}");
+                None
+            }
+        }
+    }
+
     #[allow(clippy::map_entry)]
     pub(crate) fn create(
         &mut self,
         game_id: String,
         initial_player: Player,
         settings: GameSettings,
-        letters: Vec<char>,
+        rack: Rack,
     ) -> Result<()> {
-        if self.0.contains_key(&game_id) {
+        if self.games.contains_key(&game_id) {
             Err(Error::GameConflict)
         } else {
             if !settings.is_valid() {
                 return Err(Error::InvalidGameSettings);
             }
-            let mut game = Game {
-                settings,
-                ..Default::default()
-            };
-            game.add_round(letters);
+            let mut game = Game::default();
+            game.apply(GameEvent::GameCreated { settings });
+            game.add_round(rack);
             game.add_player(initial_player)?;
-            self.0.insert(game_id, game);
+            self.games.insert(game_id, game);
             Ok(())
         }
     }
 
+    /// Recreate an exported game as a read-only replay
+    pub(crate) fn import(&mut self, game_id: String, export: GameExport) -> Result<()> {
+        if export.version != GameExport::VERSION {
+            return Err(Error::UnsupportedExportVersion);
+        }
+        if self.games.contains_key(&game_id) {
+            return Err(Error::GameConflict);
+        }
+        let mut game = Game::from_events(export.events)?;
+        game.replay = true;
+        self.games.insert(game_id, game);
+        Ok(())
+    }
+
     pub(crate) fn get(&mut self, game_id: &str) -> Result<&mut Game> {
-        self.0.get_mut(game_id).ok_or(Error::GameNotFound)
+        self.games.get_mut(game_id).ok_or(Error::GameNotFound)
+    }
+
+    /// Get a game that is being played, rather than a replay that can only be viewed
+    pub(crate) fn get_playable(&mut self, game_id: &str) -> Result<&mut Game> {
+        let game = self.get(game_id)?;
+        if game.replay {
+            return Err(Error::GameIsReplay);
+        }
+        Ok(game)
+    }
+
+    /// The public games that players can join right now
+    pub(crate) fn public_games(&self) -> Vec<GameListing> {
+        let mut listings: Vec<GameListing> = self
+            .games
+            .iter()
+            .filter(|(_, game)| game.is_open())
+            .map(|(game_id, game)| GameListing {
+                game_id: game_id.clone(),
+                players: game.players.len(),
+                language: game.settings.language.clone(),
+                mode: game.settings.mode,
+                number_of_tiles: game.settings.number_of_tiles,
+                scoring_method: game.settings.scoring_method,
+                round_time_limit_seconds: game.settings.round_time_limit_seconds,
+                max_rounds: game.settings.max_rounds,
+                club: game.settings.club.clone(),
+            })
+            .collect();
+        listings.sort_by(|a, b| a.game_id.cmp(&b.game_id));
+        listings
+    }
+
+    /// The fullest public game the player could join with settings like the ones given
+    pub(crate) fn find_open_game(&self, player: &str, settings: &GameSettings) -> Option<String> {
+        self.games
+            .iter()
+            .filter(|(_, game)| {
+                game.is_open()
+                    && !game.players.contains(player)
+                    && game.settings.is_compatible(settings)
+            })
+            .max_by_key(|(game_id, game)| (game.players.len(), Reverse(*game_id)))
+            .map(|(game_id, _)| game_id.clone())
+    }
+
+    /// A random game ID that no game is using yet
+    pub(crate) fn unused_game_id(&self) -> String {
+        loop {
+            let game_id: String = thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(GAME_ID_LENGTH)
+                .map(char::from)
+                .collect();
+            if !self.games.contains_key(&game_id) {
+                return game_id;
+            }
+        }
     }
 
     pub(crate) fn delete(&mut self, game_id: &str) {
-        self.0.remove(game_id);
+        // Games that end on their own are rated when they end, so deleting one cannot lock in a
+        // lead, and other games are rated on their scored rounds when they are deleted
+        if let Some(game) = self.games.remove(game_id) {
+            if !game.replay && !game.settings.elimination && game.settings.max_rounds.is_none() {
+                self.stats.profiles.record_game(&game);
+                self.stats.leaderboards.record_game(&game, now_ms());
+                self.save_stats();
+            }
+        }
+    }
+
+    /// Add the results of a game's round to its players' profiles once it has been scored
+    pub(crate) fn record_round(&mut self, game_id: &str, dictionary: &Dictionary) -> Result<()> {
+        let game = self.games.get(game_id).ok_or(Error::GameNotFound)?;
+        self.stats.profiles.record_round(game, dictionary);
+        self.stats
+            .leaderboards
+            .record_round(game_id, game, now_ms());
+        self.save_stats();
+        Ok(())
+    }
+
+    /// Record the best answers found for a game's round, adding them to its results if it has
+    /// already been recorded
+    pub(crate) fn set_best_answers(
+        &mut self,
+        game_id: &str,
+        round: usize,
+        best_answers: Vec<WordInfo>,
+    ) -> Result<()> {
+        let game = self.games.get_mut(game_id).ok_or(Error::GameNotFound)?;
+        let recorded = game
+            .rounds
+            .get(round)
+            .is_some_and(|round| round.scored && round.best_answers.is_empty());
+        game.set_best_answers(round, best_answers);
+        if recorded && !game.replay {
+            self.stats.profiles.record_percent_of_best(game, round);
+            self.stats
+                .leaderboards
+                .record_percent_of_best(game_id, game, round);
+            self.save_stats();
+        }
+        Ok(())
+    }
+
+    /// Update the ratings and game wins of a game's players once it is over
+    pub(crate) fn record_game(&mut self, game_id: &str) -> Result<()> {
+        let game = self.games.get(game_id).ok_or(Error::GameNotFound)?;
+        self.stats.profiles.record_game(game);
+        self.stats.leaderboards.record_game(game, now_ms());
+        self.save_stats();
+        Ok(())
+    }
+
+    pub(crate) fn profile(&self, player: &str) -> Result<&Profile> {
+        self.stats.profiles.get(player)
+    }
+
+    pub(crate) fn leaderboard(&self, query: &LeaderboardQuery) -> Leaderboard {
+        self.stats.leaderboards.get(query, now_ms())
     }
 }
 
 #[test]
 fn test_get_score() -> Result<()> {
     let mut game = Game::default();
-    let dictionary = Dictionary::new("word-list.txt");
-    game.add_round(vec!['S', 'C', 'R', 'A', 'M', 'B', 'L', 'E']);
+    let dictionary = test_dictionary();
+    game.add_round(test_rack("SCRAMBLE"));
     game.add_player(String::from("test"))?;
     assert!(game
         .answer(
@@ -391,7 +1713,7 @@ fn test_get_score() -> Result<()> {
                 player: String::from("test"),
                 answer: String::from("scr"),
             },
-            &dictionary,
+            dictionary,
         )
         .is_err_and(|e| matches!(e, Error::WordNotInDictionary)));
     assert!(game
@@ -400,7 +1722,7 @@ fn test_get_score() -> Result<()> {
                 player: String::from("test"),
                 answer: String::from("bell"),
             },
-            &dictionary,
+            dictionary,
         )
         .is_err_and(|e| matches!(e, Error::WordUsesExtraLetters)));
     game.answer(
@@ -408,13 +1730,749 @@ fn test_get_score() -> Result<()> {
             player: String::from("test"),
             answer: String::from("scramble"),
         },
-        &dictionary,
+        dictionary,
     )?;
     let mut expected = HashMap::new();
     expected.insert(String::from("test"), 14);
+    assert_eq!(game.get_score().players, expected);
+    Ok(())
+}
+
+#[test]
+fn test_difficulty_from_playable_words() {
+    assert_eq!(Difficulty::from_playable_words(60, 7), Difficulty::Easy);
+    assert_eq!(Difficulty::from_playable_words(20, 7), Difficulty::Medium);
+    assert_eq!(Difficulty::from_playable_words(3, 7), Difficulty::Hard);
+    assert_eq!(Difficulty::from_playable_words(0, 0), Difficulty::Hard);
+}
+
+#[test]
+fn test_multi_word_round() -> Result<()> {
+    let mut game = Game {
+        settings: GameSettings {
+            mode: GameMode::MultiWord,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let dictionary = test_dictionary();
+    game.add_round(remorse_rack());
+    game.add_player(String::from("test"))?;
+    let answer = |word: &str| Answer {
+        player: String::from("test"),
+        answer: String::from(word),
+    };
+    assert!(!GameSettings {
+        round_time_limit_seconds: u64::MAX,
+        ..game.settings.clone()
+    }
+    .is_valid());
+    // The clock waits for the first answer rather than starting with the round
+    assert!(game.current_round().deadline_ms.is_none());
+    game.answer(answer("remorse"), dictionary)?;
+    assert!(game.current_round().deadline_ms.is_some());
+    assert_eq!(game.current_round_state(), RoundState::CollectingAnswers);
+    game.answer(answer("more"), dictionary)?;
+    assert!(game
+        .answer(answer("MORE"), dictionary)
+        .is_err_and(|e| matches!(e, Error::WordAlreadyPlayed)));
+    for _ in 0..3 {
+        assert!(game
+            .answer(answer("mors"), dictionary)
+            .is_err_and(|e| matches!(e, Error::WordNotInDictionary)));
+    }
+    assert_eq!(game.get_score().players["test"], 9 + 6);
+    game.current_round_mut().deadline_ms = Some(now_ms());
+    assert_eq!(game.current_round_state(), RoundState::Complete);
+    assert!(game
+        .answer(answer("ore"), dictionary)
+        .is_err_and(|e| matches!(e, Error::RoundNotInCollectingAnswersState)));
+
+    // A round that runs out without answers waits for the players to start it again
+    game.add_round(game.current_round().rack());
+    assert!(game.start(String::from("test"))?);
+    assert!(!game.start(String::from("test"))?);
+    game.current_round_mut().deadline_ms = Some(now_ms());
+    game.stop_clock();
+    assert!(!game.clock_started());
+    assert_eq!(game.current_round_state(), RoundState::Start);
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_answers() -> Result<()> {
+    let dictionary = test_dictionary();
+    let play = |duplicate_answers| -> Result<Game> {
+        let mut game = Game {
+            settings: GameSettings {
+                duplicate_answers,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        game.add_round(remorse_rack());
+        let answers = [("a", "more"), ("b", "More"), ("c", "remorse")];
+        for (player, _) in answers {
+            game.add_player(String::from(player))?;
+        }
+        for (player, word) in answers {
+            game.answer(
+                Answer {
+                    player: String::from(player),
+                    answer: String::from(word),
+                },
+                dictionary,
+            )?;
+        }
+        game.score_round(dictionary);
+        Ok(game)
+    };
+
+    let game = play(DuplicateAnswers::Keep)?;
+    assert_eq!(game.get_score().players["a"], 6);
+
+    let game = play(DuplicateAnswers::Cancel)?;
+    let scores = game.get_score().players;
+    assert_eq!((scores["a"], scores["b"], scores["c"]), (0, 0, 9));
+    let answer = &game.current_round().answers[0];
+    assert_eq!(answer.word_score, 6);
+    assert_eq!(
+        answer.adjustments,
+        vec![ScoreAdjustment::Cancelled {
+            shared_with: vec![String::from("b")]
+        }]
+    );
+
+    let game = play(DuplicateAnswers::Split)?;
+    assert_eq!(game.get_score().players["b"], 3);
+    Ok(())
+}
+
+#[test]
+fn test_percent_of_best() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game {
+        settings: GameSettings {
+            percent_of_best: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    game.add_round(remorse_rack());
+    for player in ["a", "b"] {
+        game.add_player(String::from(player))?;
+    }
+    for (player, word) in [("a", "more"), ("b", "remorse")] {
+        game.answer(
+            Answer {
+                player: String::from(player),
+                answer: String::from(word),
+            },
+            dictionary,
+        )?;
+    }
+    assert!(game.current_round().best_answers.is_empty());
+    game.score_round(dictionary);
+    assert_eq!(game.current_round().best_answers[0].word, "REMORSE");
+    let scores = game.get_score().players;
+    assert_eq!((scores["a"], scores["b"]), (66, 100));
+
+    // A word left out of the best answers, like a hidden offensive word, still scores at most 100
+    game.add_round(remorse_rack());
+    let more = dictionary
+        .get_word_info_if_playable("more")
+        .unwrap()
+        .clone();
+    game.set_best_answers(1, vec![more]);
+    for player in ["a", "b"] {
+        game.answer(
+            Answer {
+                player: String::from(player),
+                answer: String::from("remorse"),
+            },
+            dictionary,
+        )?;
+    }
+    game.score_round(dictionary);
+    assert!(game.rounds[1].answers.iter().all(|a| a.score == 100));
+    Ok(())
+}
+
+#[test]
+fn test_team_scores() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game::default();
+    game.add_round(remorse_rack());
+    for player in ["a", "b", "c"] {
+        game.add_player(String::from(player))?;
+    }
+    game.create_team(String::from("red"))?;
+    game.create_team(String::from("blue"))?;
+    assert!(game
+        .create_team(String::from("red"))
+        .is_err_and(|e| matches!(e, Error::TeamConflict)));
+    game.join_team(String::from("blue"), String::from("a"))?;
+    game.join_team(String::from("red"), String::from("a"))?;
+    game.join_team(String::from("red"), String::from("b"))?;
+    game.join_team(String::from("blue"), String::from("c"))?;
+    assert!(game
+        .join_team(String::from("green"), String::from("c"))
+        .is_err_and(|e| matches!(e, Error::TeamNotFound)));
+    for (player, word) in [("a", "more"), ("b", "remorse"), ("c", "ore")] {
+        game.answer(
+            Answer {
+                player: String::from(player),
+                answer: String::from(word),
+            },
+            dictionary,
+        )?;
+    }
+    let scores = game.get_score();
+    assert_eq!((scores.teams["red"], scores.teams["blue"]), (6 + 9, 3));
+    game.settings.team_scoring = TeamScoring::Best;
+    assert_eq!(game.get_score().teams["red"], 9);
+    Ok(())
+}
+
+#[test]
+fn test_elimination() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game {
+        settings: GameSettings {
+            elimination: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let answer = |player: &str, word: &str| Answer {
+        player: String::from(player),
+        answer: String::from(word),
+    };
+    game.add_round(remorse_rack());
+    for player in ["a", "b", "c"] {
+        game.add_player(String::from(player))?;
+    }
+    game.answer(answer("a", "remorse"), dictionary)?;
+    game.answer(answer("b", "more"), dictionary)?;
+    game.answer(answer("c", "ore"), dictionary)?;
+    game.score_round(dictionary);
+    assert_eq!(game.eliminated, vec![String::from("c")]);
+    assert!(!game.is_over());
+
+    game.add_round(remorse_rack());
+    assert!(game
+        .answer(answer("c", "more"), dictionary)
+        .is_err_and(|e| matches!(e, Error::PlayerEliminated)));
+    // Both players tie on score and length, so the later answer is eliminated
+    game.answer(answer("b", "more"), dictionary)?;
+    game.answer(answer("a", "more"), dictionary)?;
+    assert_eq!(game.current_round_state(), RoundState::Complete);
+    game.score_round(dictionary);
+    assert!(game.is_over());
+    assert_eq!(game.get_score().ranking, ["b", "a", "c"]);
+    // The last round keeps the eliminated player's answer once the game is over
+    assert_eq!(game.current_round_state(), RoundState::Complete);
+    assert!(game
+        .answer(answer("b", "ore"), dictionary)
+        .is_err_and(|e| matches!(e, Error::RoundNotInCollectingAnswersState)));
+    Ok(())
+}
+
+#[test]
+fn test_speed_bonus_scoring() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game {
+        settings: GameSettings {
+            speed_bonus: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    game.add_round(remorse_rack());
+    game.add_player(String::from("a"))?;
+    // The clock waits for a player to start the round
+    assert_eq!(game.rounds[0].started_at_ms, None);
+    game.start(String::from("a"))?;
+    // Pretend the round started a minute ago, after the bonus window closed
+    game.rounds[0].started_at_ms = game.rounds[0].started_at_ms.map(|ms| ms - 60_000);
+    game.answer(
+        Answer {
+            player: String::from("a"),
+            answer: String::from("more"),
+        },
+        dictionary,
+    )?;
+    game.score_round(dictionary);
+    let answer = &game.rounds[0].answers[0];
+    assert!(answer.answer_time_ms >= 60_000);
+    assert_eq!(answer.score, answer.word_score);
+    assert!(answer.adjustments.is_empty());
+
+    // The time is taken from when the round was shown, not from the first answer
+    game.add_round(remorse_rack());
+    game.start(String::from("a"))?;
+    game.rounds[1].started_at_ms = game.rounds[1].started_at_ms.map(|ms| ms - 15_000);
+    game.answer(
+        Answer {
+            player: String::from("a"),
+            answer: String::from("more"),
+        },
+        dictionary,
+    )?;
+    game.score_round(dictionary);
+    let answer = &game.rounds[1].answers[0];
+    assert!(answer.answer_time_ms >= 15_000);
+    assert!((answer.word_score + 4..=answer.word_score + 5).contains(&answer.score));
+    Ok(())
+}
+
+#[test]
+fn test_round_constraint() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game::default();
+    game.add_round(Rack {
+        constraint: Some(RoundConstraint::MinimumLength { length: 5 }),
+        ..remorse_rack()
+    });
+    game.add_player(String::from("a"))?;
+    let answer = |word: &str| Answer {
+        player: String::from("a"),
+        answer: String::from(word),
+    };
+    assert!(game
+        .answer(answer("more"), dictionary)
+        .is_err_and(|e| matches!(e, Error::WordBreaksRoundConstraint)));
+    // Breaking the constraint does not use up a guess
+    assert!(game.current_round().guesses_used.is_empty());
+    game.answer(answer("remorse"), dictionary)?;
+
+    let letters = remorse_rack().letters;
+    let tiles = |word: &str| word.chars().map(String::from).collect::<Vec<_>>();
+    let must_use_m = RoundConstraint::MustUseTile { index: 2 };
+    assert!(must_use_m.is_met(&letters, &tiles("MORE")));
+    assert!(!must_use_m.is_met(&letters, &tiles("ORE")));
+    assert!(!must_use_m.is_met(&letters, &tiles("MM")));
+    // Either R can be placed on the highlighted second R
+    let must_use_second_r = RoundConstraint::MustUseTile { index: 4 };
+    assert!(must_use_second_r.is_met(&letters, &tiles("ORE")));
+    assert!(!must_use_second_r.is_met(&letters, &tiles("MOSE")));
+    let must_start_with_o = RoundConstraint::MustStartWith {
+        letter: String::from("O"),
+    };
+    assert!(must_start_with_o.is_met(&letters, &tiles("ORE")));
+    assert!(!must_start_with_o.is_met(&letters, &tiles("MORE")));
+    Ok(())
+}
+
+#[test]
+fn test_reroll_votes() -> Result<()> {
+    let mut game = Game::default();
+    game.add_round(test_rack("QQQZZZX"));
+    for player in ["a", "b", "c"] {
+        game.add_player(String::from(player))?;
+    }
+    assert!(!game.vote_reroll(String::from("a"))?);
+    // Voting twice does not count twice
+    assert!(!game.vote_reroll(String::from("a"))?);
+    assert!(game.vote_reroll(String::from("b"))?);
+    game.reroll(test_rack("REMORSE"));
+    assert_eq!(game.rounds.len(), 1);
+    assert_eq!(game.current_round().letters.concat(), "REMORSE");
+    assert!(game.current_round().reroll_votes.is_empty());
+    assert!(game
+        .vote_reroll(String::from("a"))
+        .is_err_and(|e| matches!(e, Error::NoRerollsLeft)));
+    Ok(())
+}
+
+#[test]
+fn test_hints() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game::default();
+    game.add_round(remorse_rack());
+    game.add_player(String::from("a"))?;
+    game.add_player(String::from("b"))?;
+    let player = || String::from("a");
+    assert!(game
+        .hint(player())
+        .is_err_and(|e| matches!(e, Error::HintNotReady)));
+    game.find_best_answers(dictionary);
+    assert_eq!(game.hint(player())?, Hint::Length { length: 7 });
     assert_eq!(
-        game.get_score(&dictionary, &ScoringMethod::Normal),
-        expected
+        game.hint(player())?,
+        Hint::FirstLetter {
+            letter: String::from("R")
+        }
     );
+    // A huge penalty takes the score to zero rather than overflowing
+    game.settings.hint_penalty = u32::MAX;
+    assert_eq!(game.get_score().players["a"], 0);
+    game.settings.hint_penalty = 2;
+    assert!(matches!(game.hint(player())?, Hint::Definition { .. }));
+    assert!(game
+        .hint(player())
+        .is_err_and(|e| matches!(e, Error::NoHintsLeft)));
+    game.answer(
+        Answer {
+            player: player(),
+            answer: String::from("remorse"),
+        },
+        dictionary,
+    )?;
+    assert!(game
+        .hint(player())
+        .is_err_and(|e| matches!(e, Error::AlreadyAnswered)));
+    // Three hints at two points each come off the score of 9
+    assert_eq!(game.get_score().players["a"], 3);
+
+    game.settings.hints = false;
+    assert!(game
+        .hint(player())
+        .is_err_and(|e| matches!(e, Error::HintsDisabled)));
+    Ok(())
+}
+
+#[test]
+fn test_challenges() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game {
+        settings: GameSettings {
+            allow_challenged_words: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let answer = |player: &str, word: &str| Answer {
+        player: String::from(player),
+        answer: String::from(word),
+    };
+    let challenge = |player: &str, round, answer_player: &str, word: &str| ChallengeData {
+        player: String::from(player),
+        round,
+        answer_player: String::from(answer_player),
+        answer: String::from(word),
+    };
+    let vote = |player: &str, agree| VoteData {
+        player: String::from(player),
+        agree,
+    };
+    game.add_round(remorse_rack());
+    for player in ["a", "b", "c"] {
+        game.add_player(String::from(player))?;
+    }
+    game.answer(answer("a", "remorse"), dictionary)?;
+    game.answer(answer("b", "more"), dictionary)?;
+    assert!(game.answer(answer("c", "ermos"), dictionary).is_err());
+    assert!(game
+        .challenge(challenge("a", 0, "b", "more"), dictionary)
+        .is_err_and(|e| matches!(e, Error::RoundNotComplete)));
+    game.answer(answer("c", "ermos"), dictionary)?;
+    assert!(game
+        .challenge(challenge("a", 0, "b", "more"), dictionary)
+        .is_err_and(|e| matches!(e, Error::RoundNotComplete)));
+    game.score_round(dictionary);
+
+    // Two of three players agree to reject a word
+    let id = game.challenge(challenge("b", 0, "a", "remorse"), dictionary)?;
+    game.vote_on_challenge(id, vote("c", true), dictionary)?;
+    assert_eq!(game.challenges[id].outcome, Some(ChallengeOutcome::Upheld));
+    assert!(game
+        .vote_on_challenge(id, vote("a", false), dictionary)
+        .is_err_and(|e| matches!(e, Error::ChallengeResolved)));
+    assert_eq!(game.get_score().players["a"], 0);
+
+    // Two of three players refuse to accept a guess
+    let id = game.challenge(challenge("c", 0, "c", "ermos"), dictionary)?;
+    game.vote_on_challenge(id, vote("a", false), dictionary)?;
+    game.vote_on_challenge(id, vote("b", false), dictionary)?;
+    assert_eq!(
+        game.challenges[id].outcome,
+        Some(ChallengeOutcome::Dismissed)
+    );
+    assert_eq!(game.get_score().players["c"], 0);
+    // An answer is only challenged once, whatever the outcome
+    assert!(game
+        .challenge(challenge("a", 0, "c", "ermos"), dictionary)
+        .is_err_and(|e| matches!(e, Error::ChallengeConflict)));
+
+    // Once accepted the guess scores and becomes playable, and a bot's vote is not waited for
+    game.add_round(remorse_rack());
+    game.add_bot(String::from("bot"), BotSettings::default())?;
+    game.answer(answer("bot", "re"), dictionary)?;
+    game.answer(answer("a", "more"), dictionary)?;
+    game.answer(answer("b", "ore"), dictionary)?;
+    assert!(game.answer(answer("c", "ermos"), dictionary).is_err());
+    game.answer(answer("c", "ermos"), dictionary)?;
+    game.score_round(dictionary);
+    let id = game.challenge(challenge("c", 1, "c", "ermos"), dictionary)?;
+    game.vote_on_challenge(id, vote("b", true), dictionary)?;
+    assert_eq!(game.challenges[id].kind, ChallengeKind::Accept);
+    let accepted = game.rounds[1]
+        .all_answers()
+        .find(|a| a.player == "c")
+        .unwrap();
+    assert!(accepted.is_accepted());
+    assert_eq!(accepted.score, 7);
+    game.add_round(remorse_rack());
+    game.answer(answer("c", "ermos"), dictionary)?;
+    assert_eq!(game.current_round().answers[0].score, 7);
+    Ok(())
+}
+
+#[test]
+fn test_export_import() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut games = Games::default();
+    let rack = remorse_rack();
+    games.create(
+        String::from("original"),
+        String::from("a"),
+        GameSettings::default(),
+        rack,
+    )?;
+    let game = games.get_playable("original")?;
+    let answer = |word: &str| Answer {
+        player: String::from("a"),
+        answer: String::from(word),
+    };
+    assert!(game.answer(answer("ermos"), dictionary).is_err());
+    game.answer(answer("more"), dictionary)?;
+    let json = serde_json::to_string(&GameExport::new(game)).unwrap();
+
+    let export: GameExport = serde_json::from_str(&json).unwrap();
+    games.import(String::from("replay"), export)?;
+    let replay = games.get("replay")?;
+    assert!(replay.replay);
+    assert_eq!(replay.current_round().rejected_guesses[0].guess, "ermos");
+    assert_eq!(replay.get_score().players["a"], 6);
+    assert!(games
+        .get_playable("replay")
+        .is_err_and(|e| matches!(e, Error::GameIsReplay)));
+
+    let mut export: GameExport = serde_json::from_str(&json).unwrap();
+    export.version += 1;
+    assert!(games
+        .import(String::from("future"), export)
+        .is_err_and(|e| matches!(e, Error::UnsupportedExportVersion)));
+
+    // A log has to create the game before anything can happen in it
+    let mut export: GameExport = serde_json::from_str(&json).unwrap();
+    export.events.remove(0);
+    assert!(games
+        .import(String::from("malformed"), export)
+        .is_err_and(|e| matches!(e, Error::InvalidExport)));
+    let mut export: GameExport = serde_json::from_str(&json).unwrap();
+    export.events.push(GameEvent::BestAnswersFound {
+        round: 1,
+        best_answers: Vec::new(),
+    });
+    assert!(games
+        .import(String::from("malformed"), export)
+        .is_err_and(|e| matches!(e, Error::InvalidExport)));
+    // A game with no rounds would have nothing to show
+    let mut export: GameExport = serde_json::from_str(&json).unwrap();
+    export.events.truncate(1);
+    assert!(games
+        .import(String::from("malformed"), export)
+        .is_err_and(|e| matches!(e, Error::InvalidExport)));
+    let mut export: GameExport = serde_json::from_str(&json).unwrap();
+    export.events.clear();
+    assert!(games
+        .import(String::from("malformed"), export)
+        .is_err_and(|e| matches!(e, Error::InvalidExport)));
+    assert!(games.get("malformed").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_game_from_events() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut games = Games::default();
+    games.create(
+        String::from("game"),
+        String::from("a"),
+        GameSettings::default(),
+        remorse_rack(),
+    )?;
+    let game = games.get_playable("game")?;
+    game.add_player(String::from("b"))?;
+    game.create_team(String::from("team"))?;
+    game.join_team(String::from("team"), String::from("b"))?;
+    let answer = |player: &str, word: &str| Answer {
+        player: String::from(player),
+        answer: String::from(word),
+    };
+    game.find_best_answers(dictionary);
+    game.hint(String::from("a"))?;
+    assert!(game.answer(answer("a", "ermos"), dictionary).is_err());
+    game.answer(answer("a", "remorse"), dictionary)?;
+    game.answer(answer("b", "more"), dictionary)?;
+    game.score_round(dictionary);
+    game.add_round(remorse_rack());
+    game.answer(answer("b", "ore"), dictionary)?;
+
+    let rebuilt = Game::from_events(game.events.clone())?;
+    assert_eq!(rebuilt.events.len(), game.events.len());
+    assert_eq!(rebuilt.players, game.players);
+    assert_eq!(rebuilt.teams, game.teams);
+    assert_eq!(
+        serde_json::to_value(&rebuilt.rounds).unwrap(),
+        serde_json::to_value(&game.rounds).unwrap()
+    );
+    assert_eq!(rebuilt.get_score().players, game.get_score().players);
+    assert_eq!(rebuilt.get_score().teams["team"], 9);
+    Ok(())
+}
+
+#[test]
+fn test_public_games() -> Result<()> {
+    let mut games = Games::default();
+    let public = GameSettings {
+        public: true,
+        ..Default::default()
+    };
+    games.create(
+        String::from("public"),
+        String::from("a"),
+        public.clone(),
+        remorse_rack(),
+    )?;
+    games.create(
+        String::from("private"),
+        String::from("a"),
+        GameSettings::default(),
+        remorse_rack(),
+    )?;
+    let listings = games.public_games();
+    assert_eq!(listings.len(), 1);
+    assert_eq!(listings[0].game_id, "public");
+    assert_eq!(listings[0].players, 1);
+
+    assert_eq!(
+        games.find_open_game("b", &public).as_deref(),
+        Some("public")
+    );
+    // Players are not matched into a game they are already in
+    assert_eq!(games.find_open_game("a", &public), None);
+    let multi_word = GameSettings {
+        mode: GameMode::MultiWord,
+        ..public
+    };
+    assert_eq!(games.find_open_game("b", &multi_word), None);
+    assert!(!games.games.contains_key(&games.unused_game_id()));
+    Ok(())
+}
+
+#[test]
+fn test_bot_forfeit() -> Result<()> {
+    let dictionary = test_dictionary();
+    let mut game = Game::default();
+    let rack = Rack {
+        constraint: Some(RoundConstraint::MinimumLength { length: 7 }),
+        ..remorse_rack()
+    };
+    game.add_round(rack.clone());
+    game.add_player(String::from("a"))?;
+    let bot = BotSettings {
+        max_word_length: Some(5),
+        ..Default::default()
+    };
+    game.add_bot(String::from("bot"), bot.clone())?;
+    // Only REMORSE is long enough, which is too long for the bot
+    let words = dictionary.get_best_words_now(&rack, usize::MAX, &game.settings);
+    assert!(bot.choose_word(&words).is_none());
+    game.forfeit(String::from("bot"))?;
+    assert_eq!(game.current_round_state(), RoundState::CollectingAnswers);
+    game.answer(
+        Answer {
+            player: String::from("a"),
+            answer: String::from("remorse"),
+        },
+        dictionary,
+    )?;
+    assert_eq!(game.current_round_state(), RoundState::Complete);
+    game.score_round(dictionary);
+    assert_eq!(game.get_score().players["bot"], 0);
+    assert_eq!(game.get_score().players["a"], 9);
+    Ok(())
+}
+
+#[test]
+fn test_stats_file() -> Result<()> {
+    let dictionary = test_dictionary();
+    let path = std::env::temp_dir().join(format!("scramble-stats-{}.json", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let mut games = Games::with_stats_file(path.clone());
+    let rack = remorse_rack();
+    games.create(
+        String::from("game"),
+        String::from("a"),
+        GameSettings::default(),
+        rack,
+    )?;
+    let game = games.get_playable("game")?;
+    game.answer(
+        Answer {
+            player: String::from("a"),
+            answer: String::from("more"),
+        },
+        dictionary,
+    )?;
+    game.score_round(dictionary);
+    games.record_round("game", dictionary)?;
+    let (stats_path, json) = games.take_stats_to_save().unwrap();
+    write_stats(&stats_path, &json);
+    assert!(games.take_stats_to_save().is_none());
+
+    // The statistics outlive the server that recorded them
+    let restarted = Games::with_stats_file(path.clone());
+    fs::remove_file(&path).unwrap();
+    assert_eq!(restarted.profile("a")?.total_score, 6);
+    assert!(restarted.games.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_game_tile_set() -> Result<()> {
+    let dictionary = test_dictionary();
+    // The game adds a tile with two letters on it to the usual tiles
+    let mut tiles = serde_json::to_value(TileSet::default()).unwrap();
+    tiles
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({ "letter": "RE", "points": 2, "count": 2 }));
+    let mut game = Game {
+        settings: GameSettings {
+            tile_set: Some(serde_json::from_value(tiles).unwrap()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let rack = Rack {
+        letters: ["RE", "M", "O", "S"].map(String::from).to_vec(),
+        ..test_rack("")
+    };
+    game.add_round(rack.clone());
+    game.add_player(String::from("a"))?;
+    let answer = |word: &str| Answer {
+        player: String::from("a"),
+        answer: String::from(word),
+    };
+    // RE is two letters but only one tile
+    assert!(game
+        .answer(answer("re"), dictionary)
+        .is_err_and(|e| matches!(e, Error::WordMustBeAtLeastTwoLetters)));
+    let best_words = dictionary.get_best_words_now(&rack, usize::MAX, &game.settings);
+    assert!(best_words.iter().any(|info| info.word == "MORE"));
+    game.answer(answer("more"), dictionary)?;
+    assert_eq!(game.current_round().answers[0].score, 3 + 1 + 2);
+
+    // With separate R and E tiles, MORE is spelled with them instead of the RE tile
+    let rack = test_rack("MORE");
+    game.add_round(rack.clone());
+    let best_words = dictionary.get_best_words_now(&rack, usize::MAX, &game.settings);
+    assert!(best_words.iter().any(|info| info.word == "MORE"));
+    game.answer(answer("more"), dictionary)?;
+    assert_eq!(game.current_round().answers[0].score, 3 + 1 + 1 + 1);
     Ok(())
 }